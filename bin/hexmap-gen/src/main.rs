@@ -0,0 +1,315 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
+use hexx::{HexOrientation, OffsetHexMode};
+
+use hexmap_worldgen::{
+    geojson::{self, GeoJsonParams},
+    heightmap::Heightmap,
+    image::{self, ImageParams},
+    names::NameGenerator,
+    save::{self, TerrainSource, WorldFile},
+    terrain::{ErosionParams, TerrainParams, Topology},
+    tiled::{self, TiledParams},
+    world,
+};
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Format {
+    Png,
+    Tmx,
+    Tmj,
+    Geojson,
+    // The world save format the app loads
+    World,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Tmx => "tmx",
+            Format::Tmj => "tmj",
+            Format::Geojson => "geojson",
+            Format::World => "json",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Orientation {
+    Flat,
+    Pointy,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Wrapping {
+    Flat,
+    Cylinder,
+    Torus,
+}
+
+/// Generates worlds without a window and writes them to disk
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    #[arg(long, default_value_t = 170)]
+    width: i32,
+
+    #[arg(long, default_value_t = 100)]
+    height: i32,
+
+    #[arg(long, default_value_t = 1.2)]
+    scale_factor: f64,
+
+    /// Seed of the first world, picked at random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of worlds to generate, on consecutive seeds
+    #[arg(long, default_value_t = 1)]
+    count: u64,
+
+    #[arg(long, value_enum, default_value_t = Format::Png)]
+    format: Format,
+
+    /// Where to write each world, with `{seed}` replaced by its seed. Defaults to
+    /// `map-{seed}` with the extension of the format.
+    #[arg(long)]
+    output: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = Orientation::Flat)]
+    orientation: Orientation,
+
+    #[arg(long, value_enum, default_value_t = Wrapping::Cylinder)]
+    topology: Wrapping,
+
+    /// Take elevations from a grayscale PNG instead of generating them
+    #[arg(long)]
+    heightmap: Option<PathBuf>,
+
+    /// A CSV of settlement names with a header row, like the app's `settlement_names.csv`, to
+    /// make up new names from
+    #[arg(long)]
+    names: Option<PathBuf>,
+
+    /// Let made-up names repeat names from the CSV
+    #[arg(long, requires = "names")]
+    source_names: bool,
+
+    /// Size of each hex in PNG images and Tiled tilesets, twice the length of a side
+    #[arg(long, default_value_t = 12)]
+    pixels_per_hex: u32,
+}
+
+impl Args {
+    // Batches need a separate file for each world
+    fn check(&self) -> Result<(), clap::Error> {
+        if self.count > 1
+            && self
+                .output
+                .as_ref()
+                .is_some_and(|output| !output.contains("{seed}"))
+        {
+            return Err(Args::command().error(
+                ErrorKind::ArgumentConflict,
+                "--output must contain `{seed}` to generate more than one world",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, seed: u64) -> PathBuf {
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => format!("map-{{seed}}.{}", self.format.extension()),
+        };
+
+        PathBuf::from(output.replace("{seed}", &seed.to_string()))
+    }
+}
+
+fn read_names(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .skip(1)
+        .map(|line| line.trim().trim_matches('"').to_owned())
+        .filter(|name| !name.is_empty())
+        .collect())
+}
+
+fn generate(
+    args: &Args,
+    seed: u64,
+    heightmap: Option<&Heightmap>,
+    names: Option<&NameGenerator>,
+) -> WorldFile {
+    let terrain_params = TerrainParams::new(args.width, args.height, args.scale_factor, seed)
+        .with_orientation(match args.orientation {
+            Orientation::Flat => HexOrientation::Flat,
+            Orientation::Pointy => HexOrientation::Pointy,
+        })
+        .with_offset_mode(OffsetHexMode::Even)
+        .with_topology(match args.topology {
+            Wrapping::Flat => Topology::Flat,
+            Wrapping::Cylinder => Topology::Cylinder,
+            Wrapping::Torus => Topology::Torus,
+        })
+        .with_erosion(ErosionParams::default())
+        .with_depression_filling(0.02);
+
+    // The same generation as the app, so a seed generates the same world in both
+    match heightmap {
+        Some(heightmap) => world::generate_from_heightmap(terrain_params, heightmap, names),
+        None => world::generate(TerrainSource::Map(terrain_params), names),
+    }
+}
+
+fn write(args: &Args, world: &WorldFile, path: &Path) -> Result<(), Box<dyn Error>> {
+    let terrain = world.terrain();
+    let settlements = world.settlements();
+
+    match args.format {
+        Format::Png => {
+            let image = image::render(
+                terrain,
+                ImageParams::new(args.pixels_per_hex)
+                    .with_zone_borders(world.zones())
+                    .with_settlements(settlements.iter().map(|settlement| settlement.hex)),
+            );
+
+            image.write_png(BufWriter::new(File::create(path)?))?;
+        }
+        Format::Tmx | Format::Tmj => {
+            let map = tiled::export(
+                terrain,
+                TiledParams::new(args.pixels_per_hex / 2).with_settlements(
+                    settlements
+                        .iter()
+                        .map(|settlement| (settlement.hex, settlement.name.clone())),
+                ),
+            );
+
+            // The format is picked here rather than from the extension of `path`
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("map");
+            let tileset_source = format!("{stem}.tileset.png");
+
+            map.tileset().write_png(BufWriter::new(File::create(
+                path.with_file_name(&tileset_source),
+            )?))?;
+
+            let writer = BufWriter::new(File::create(path)?);
+
+            match args.format {
+                Format::Tmx => map.write_tmx(writer, &tileset_source)?,
+                _ => map.write_tmj(writer, &tileset_source)?,
+            }
+        }
+        Format::Geojson => {
+            let collection = geojson::export(
+                terrain,
+                GeoJsonParams::new()
+                    .with_settlements(
+                        settlements
+                            .iter()
+                            .map(|settlement| (settlement.hex, settlement.name.clone())),
+                    )
+                    .with_zones(world.zones()),
+            );
+
+            serde_json::to_writer(BufWriter::new(File::create(path)?), &collection)?;
+        }
+        Format::World => save::save(world, File::create(path)?)?,
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    if let Err(error) = args.check() {
+        error.exit();
+    }
+
+    let heightmap = args
+        .heightmap
+        .as_deref()
+        .map(|path| Heightmap::from_png(File::open(path)?))
+        .transpose()?;
+
+    let names = args
+        .names
+        .as_deref()
+        .map(|path| {
+            let names = NameGenerator::new(read_names(path)?);
+
+            Ok::<_, Box<dyn Error>>(if args.source_names {
+                names.with_source_names()
+            } else {
+                names
+            })
+        })
+        .transpose()?;
+
+    let first_seed = args.seed.unwrap_or_else(rand::random);
+
+    for offset in 0..args.count {
+        let seed = first_seed.wrapping_add(offset);
+        let path = args.path(seed);
+
+        let world = generate(&args, seed, heightmap.as_ref(), names.as_ref());
+        write(&args, &world, &path)?;
+
+        println!(
+            "{}: seed {seed}, {} settlements",
+            path.display(),
+            world.settlements().len()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(arguments: &[&str]) -> Args {
+        Args::try_parse_from(["hexmap-gen"].iter().chain(arguments)).unwrap()
+    }
+
+    #[test]
+    fn each_world_gets_its_own_path() {
+        assert_eq!(args(&[]).path(7), PathBuf::from("map-7.png"));
+        assert_eq!(
+            args(&["--format", "tmx"]).path(u64::MAX),
+            PathBuf::from(format!("map-{}.tmx", u64::MAX))
+        );
+        assert_eq!(
+            args(&["--output", "worlds/{seed}/world.xml"]).path(3),
+            PathBuf::from("worlds/3/world.xml")
+        );
+
+        assert!(args(&["--output", "world.png"]).check().is_ok());
+        assert!(
+            args(&["--count", "2", "--output", "{seed}.png"])
+                .check()
+                .is_ok()
+        );
+        assert!(
+            args(&["--count", "2", "--output", "world.png"])
+                .check()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use hexmap_worldgen::{
+    pathfinding::ZoneCost,
+    settlements::{self, SettlementParams},
+    terrain::{self, TerrainParams},
+    territories,
+};
+
+fn territories(c: &mut Criterion) {
+    // The size of map the app generates
+    let terrain = terrain::generate(TerrainParams::new(170, 100, 1.2, 42));
+    let settlements = settlements::generate(&terrain, SettlementParams::new(7))
+        .map(|settlement| settlement.hex)
+        .collect::<Vec<_>>();
+
+    c.bench_function("territories", |b| {
+        b.iter(|| territories::generate(&terrain, &settlements, ZoneCost))
+    });
+}
+
+criterion_group!(benches, territories);
+criterion_main!(benches);
@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use hexx::{Hex, HexLayout, Vec2};
+use serde_json::{Value, json};
+
+use crate::terrain::{GeneratedTerrain, TerrainType};
+
+#[derive(Debug, Clone, Default)]
+pub struct GeoJsonParams {
+    settlements: Vec<(Hex, String)>,
+    zones: HashMap<Hex, usize>,
+}
+
+impl GeoJsonParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_settlements<Name: Into<String>>(
+        self,
+        settlements: impl IntoIterator<Item = (Hex, Name)>,
+    ) -> Self {
+        Self {
+            settlements: settlements
+                .into_iter()
+                .map(|(hex, name)| (hex, name.into()))
+                .collect(),
+            ..self
+        }
+    }
+
+    // Assigns each hex to the zone of the settlement at that index
+    pub fn with_zones(self, zones: impl IntoIterator<Item = (Hex, usize)>) -> Self {
+        Self {
+            zones: zones.into_iter().collect(),
+            ..self
+        }
+    }
+}
+
+type Ring = Vec<Vec2>;
+
+fn signed_area(ring: &[Vec2]) -> f32 {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum::<f32>()
+        / 2.
+}
+
+fn contains(ring: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+// The corners of a hex, anticlockwise
+fn corners(layout: &HexLayout, hex: Hex) -> Ring {
+    let mut corners = layout.hex_corners(hex).to_vec();
+
+    if signed_area(&corners) < 0. {
+        corners.reverse();
+    }
+
+    corners
+}
+
+// Traces the outline of a set of hexes. Each hex contributes the edges it doesn't share with
+// another member, and since they all run anticlockwise, joining them up end to end gives
+// anticlockwise outer rings and clockwise holes.
+fn outline(layout: &HexLayout, hexes: &[Hex]) -> Vec<(Ring, Vec<Ring>)> {
+    // Corners are shared between hexes, but only up to rounding
+    let key = |point: Vec2| {
+        (
+            (point.x * 64.).round() as i64,
+            (point.y * 64.).round() as i64,
+        )
+    };
+
+    let members = hexes.iter().copied().collect::<HashSet<_>>();
+    let mut edges = BTreeMap::new();
+
+    for hex in hexes {
+        let centre = layout.hex_to_world_pos(*hex);
+        let corners = corners(layout, *hex);
+
+        for (a, b) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            let across = layout.world_pos_to_hex(centre + (a.midpoint(*b) - centre) * 2.);
+
+            if !members.contains(&across) {
+                edges.insert(key(*a), *b);
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+
+    while let Some(start) = edges.keys().next().copied() {
+        let mut ring = Vec::new();
+        let mut current = start;
+
+        while let Some(next) = edges.remove(&current) {
+            ring.push(next);
+            current = key(next);
+        }
+
+        rings.push(ring);
+    }
+
+    let (outer, holes): (Vec<_>, Vec<_>) =
+        rings.into_iter().partition(|ring| signed_area(ring) > 0.);
+
+    let mut polygons = outer
+        .into_iter()
+        .map(|ring| (ring, Vec::new()))
+        .collect::<Vec<_>>();
+
+    for hole in holes {
+        if let Some((_, holes)) = polygons
+            .iter_mut()
+            .find(|(outer, _)| contains(outer, hole[0]))
+        {
+            holes.push(hole);
+        }
+    }
+
+    polygons
+}
+
+fn ring_coordinates(ring: &[Vec2]) -> Value {
+    // Rings are closed by repeating their first point
+    ring.iter()
+        .chain(ring.first())
+        .map(|point| point_coordinates(*point))
+        .collect()
+}
+
+fn point_coordinates(point: Vec2) -> Value {
+    let round = |value: f32| (value as f64 * 1e4).round() / 1e4;
+    json!([round(point.x), round(point.y)])
+}
+
+fn multi_polygon(polygons: &[(Ring, Vec<Ring>)]) -> Value {
+    json!({
+        "type": "MultiPolygon",
+        "coordinates": polygons
+            .iter()
+            .map(|(outer, holes)| {
+                std::iter::once(outer)
+                    .chain(holes)
+                    .map(|ring| ring_coordinates(ring))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+// Builds a feature collection with a point for each settlement, a merged polygon for each zone and
+// the hexes of each terrain type. Coordinates are in the terrain's layout, with y pointing north.
+// Nothing wraps in GeoJSON, so anything crossing a seam is split along it.
+pub fn export(
+    terrain: &GeneratedTerrain,
+    GeoJsonParams { settlements, zones }: GeoJsonParams,
+) -> Value {
+    let layout = terrain.layout();
+    let mut features = Vec::new();
+
+    for terrain_type in TerrainType::ALL {
+        let hexes = terrain
+            .tiles()
+            .filter(|(_, tile)| *tile == terrain_type)
+            .map(|(hex, _)| (corners(layout, hex), Vec::new()))
+            .collect::<Vec<_>>();
+
+        if hexes.is_empty() {
+            continue;
+        }
+
+        features.push(json!({
+            "type": "Feature",
+            "properties": {
+                "kind": "terrain",
+                "terrain": format!("{terrain_type:?}"),
+            },
+            "geometry": multi_polygon(&hexes),
+        }));
+    }
+
+    let mut zone_hexes = HashMap::<usize, Vec<Hex>>::new();
+
+    for (hex, _) in terrain.tiles() {
+        if let Some(zone) = zones.get(&terrain.wrap(hex)) {
+            zone_hexes.entry(*zone).or_default().push(hex);
+        }
+    }
+
+    let mut zone_hexes = zone_hexes.into_iter().collect::<Vec<_>>();
+    zone_hexes.sort_by_key(|(zone, _)| *zone);
+
+    for (zone, hexes) in zone_hexes {
+        let mut properties = json!({
+            "kind": "zone",
+            "zone": zone,
+        });
+
+        if let Some((_, name)) = settlements.get(zone) {
+            properties["settlement"] = name.clone().into();
+        }
+
+        features.push(json!({
+            "type": "Feature",
+            "properties": properties,
+            "geometry": multi_polygon(&outline(layout, &hexes)),
+        }));
+    }
+
+    for (hex, name) in settlements {
+        features.push(json!({
+            "type": "Feature",
+            "properties": {
+                "kind": "settlement",
+                "name": name,
+            },
+            "geometry": {
+                "type": "Point",
+                "coordinates": point_coordinates(layout.hex_to_world_pos(terrain.wrap(hex))),
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams};
+
+    #[test]
+    fn zone_outlines_cover_their_hexes() {
+        let terrain = terrain::generate(TerrainParams::new(20, 12, 1.2, 5));
+        let layout = terrain.layout();
+
+        let hex_area = signed_area(&corners(layout, Hex::ZERO));
+
+        // A band of hexes with a hole punched through the middle, and a zone across the seam
+        let band = terrain
+            .tiles()
+            .map(|(hex, _)| hex)
+            .filter(|hex| (3..9).contains(&terrain.grid().hex_to_cell(*hex)[0]))
+            .filter(|hex| *hex != terrain.grid().cell_to_hex([5, 6]))
+            .collect::<Vec<_>>();
+
+        let polygons = outline(layout, &band);
+        let area = polygons
+            .iter()
+            .map(|(outer, holes)| {
+                signed_area(outer) + holes.iter().map(|hole| signed_area(hole)).sum::<f32>()
+            })
+            .sum::<f32>();
+
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].1.len(), 1);
+        assert!((area - hex_area * band.len() as f32).abs() < 1e-2);
+
+        let seam = terrain
+            .tiles()
+            .map(|(hex, _)| hex)
+            .filter(|hex| [1, 20].contains(&terrain.grid().hex_to_cell(*hex)[0]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(outline(layout, &seam).len(), 2);
+    }
+}
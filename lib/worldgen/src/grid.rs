@@ -0,0 +1,209 @@
+use hexx::{EdgeDirection, Hex, HexOrientation, OffsetHexMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    // Nothing wraps, every edge of the map is an edge of the world
+    Flat,
+    // The left and right edges of the map join up
+    Cylinder,
+    // Both the left and right and the top and bottom edges of the map join up
+    Torus,
+}
+
+impl Topology {
+    pub fn wraps_x(&self) -> bool {
+        match self {
+            Topology::Flat => false,
+            Topology::Cylinder => true,
+            Topology::Torus => true,
+        }
+    }
+
+    pub fn wraps_y(&self) -> bool {
+        match self {
+            Topology::Flat => false,
+            Topology::Cylinder => false,
+            Topology::Torus => true,
+        }
+    }
+}
+
+// The rectangle of hexes a map covers, numbered by column and row, and how its edges join up
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HexGrid {
+    width: i32,
+    height: i32,
+    orientation: HexOrientation,
+    offset_mode: OffsetHexMode,
+    topology: Topology,
+}
+
+impl HexGrid {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            orientation: HexOrientation::Flat,
+            offset_mode: OffsetHexMode::Even,
+            topology: Topology::Cylinder,
+        }
+    }
+
+    pub fn with_orientation(self, orientation: HexOrientation, offset_mode: OffsetHexMode) -> Self {
+        Self {
+            orientation,
+            offset_mode,
+            ..self
+        }
+    }
+
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn orientation(&self) -> HexOrientation {
+        self.orientation
+    }
+
+    pub fn offset_mode(&self) -> OffsetHexMode {
+        self.offset_mode
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn len(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn offset(&self, hex: Hex) -> [i32; 2] {
+        hex.to_offset_coordinates(self.offset_mode, self.orientation)
+    }
+
+    // Flat-top maps have always numbered their rows the way `shapes::flat_rectangle` does, which
+    // starts odd columns a row further down. That only lines up with the even offset mode, and
+    // pointy-top maps have nothing to match.
+    fn stagger(&self, x: i32) -> i32 {
+        match (self.orientation, self.offset_mode) {
+            (HexOrientation::Flat, OffsetHexMode::Even) => x.rem_euclid(2),
+            _ => 0,
+        }
+    }
+
+    // Converts to and from the 1-based column and row of a hex within the rectangle
+    pub fn cell_to_hex(&self, [x, y]: [i32; 2]) -> Hex {
+        Hex::from_offset_coordinates([x, y + self.stagger(x)], self.offset_mode, self.orientation)
+    }
+
+    pub fn hex_to_cell(&self, hex: Hex) -> [i32; 2] {
+        let [x, y] = self.offset(hex);
+        [x, y - self.stagger(x)]
+    }
+
+    pub fn index_to_cell(&self, index: usize) -> [i32; 2] {
+        [
+            (index as i32 / self.height) + 1,
+            (index as i32 % self.height) + 1,
+        ]
+    }
+
+    pub fn index_to_hex(&self, index: usize) -> Hex {
+        self.cell_to_hex(self.index_to_cell(index))
+    }
+
+    pub fn hex_to_index(&self, hex: Hex) -> Option<usize> {
+        let [x, y] = self.hex_to_cell(hex);
+
+        if x < 1 || x > self.width || y < 1 || y > self.height {
+            return None;
+        }
+
+        Some(((x - 1) * self.height + (y - 1)) as usize)
+    }
+
+    pub fn wrap(&self, hex: Hex) -> Hex {
+        let [mut x, mut y] = self.hex_to_cell(hex);
+
+        if self.topology.wraps_x() {
+            x = (x - 1).rem_euclid(self.width) + 1;
+        }
+
+        if self.topology.wraps_y() {
+            y = (y - 1).rem_euclid(self.height) + 1;
+        }
+
+        self.cell_to_hex([x, y])
+    }
+
+    // The fewest steps between two hexes, counting routes across the seams
+    pub fn distance(&self, a: Hex, b: Hex) -> u32 {
+        let [x, y] = self.hex_to_cell(b);
+
+        let shifts = |wraps: bool, period: i32| {
+            [-period, 0, period]
+                .into_iter()
+                .filter(move |shift| wraps || *shift == 0)
+        };
+
+        shifts(self.topology.wraps_x(), self.width)
+            .flat_map(|dx| shifts(self.topology.wraps_y(), self.height).map(move |dy| [dx, dy]))
+            .map(|[dx, dy]| a.unsigned_distance_to(self.cell_to_hex([x + dx, y + dy])))
+            .min()
+            .unwrap()
+    }
+
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        self.index_to_hex(index)
+            .all_neighbors()
+            .into_iter()
+            .filter_map(|hex| self.hex_to_index(self.wrap(hex)))
+    }
+
+    pub fn neighbour_directions(
+        &self,
+        index: usize,
+    ) -> impl Iterator<Item = (EdgeDirection, usize)> {
+        let hex = self.index_to_hex(index);
+
+        EdgeDirection::ALL_DIRECTIONS
+            .into_iter()
+            .filter_map(move |direction| {
+                self.hex_to_index(self.wrap(hex.neighbor(direction)))
+                    .map(|neighbour| (direction, neighbour))
+            })
+    }
+}
+
+// The cells of a map and how they connect, so the passes that only care about neighbours can run
+// over both the hex grid and the sphere
+pub(crate) trait CellGraph {
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize>;
+
+    // Whether the cell is on the edge of the map, which water can drain away over
+    fn is_edge(&self, index: usize) -> bool;
+}
+
+impl CellGraph for HexGrid {
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        HexGrid::neighbours(self, index)
+    }
+
+    fn is_edge(&self, index: usize) -> bool {
+        HexGrid::neighbours(self, index).count() < 6
+    }
+}
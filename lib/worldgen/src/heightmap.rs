@@ -0,0 +1,140 @@
+#[cfg(feature = "image")]
+use std::io::Read;
+
+use hexx::Vec2;
+
+// A grid of heights from 0 to 1, row by row from the top left
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    width: u32,
+    height: u32,
+    values: Vec<f64>,
+}
+
+impl Heightmap {
+    pub fn new(width: u32, height: u32, values: Vec<f64>) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(values.len(), (width * height) as usize);
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    // Reads the brightness of each pixel, with colour images converted to grayscale
+    #[cfg(feature = "image")]
+    pub fn from_png(reader: impl Read) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let channels = info.color_type.samples();
+        let bytes = match info.bit_depth {
+            png::BitDepth::Sixteen => 2,
+            _ => 1,
+        };
+
+        let sample = |pixel: &[u8], channel: usize| match bytes {
+            2 => u16::from_be_bytes([pixel[channel * 2], pixel[channel * 2 + 1]]) as f64 / 65535.,
+            _ => pixel[channel] as f64 / 255.,
+        };
+
+        let values = buffer[..info.buffer_size()]
+            .chunks(info.line_size)
+            .flat_map(|line| {
+                line[..info.width as usize * channels * bytes].chunks(channels * bytes)
+            })
+            .map(|pixel| match info.color_type {
+                png::ColorType::Rgb | png::ColorType::Rgba => {
+                    0.2126 * sample(pixel, 0)
+                        + 0.7152 * sample(pixel, 1)
+                        + 0.0722 * sample(pixel, 2)
+                }
+                _ => sample(pixel, 0),
+            })
+            .collect();
+
+        Ok(Self::new(info.width, info.height, values))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Blends between the four nearest pixels, with `x` and `y` running from 0 to 1 across the
+    // centres of the pixels at the edges
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        let x = x.clamp(0., 1.) * (self.width - 1) as f64;
+        let y = y.clamp(0., 1.) * (self.height - 1) as f64;
+
+        let (left, top) = (x.floor() as u32, y.floor() as u32);
+        let (right, bottom) = (
+            (left + 1).min(self.width - 1),
+            (top + 1).min(self.height - 1),
+        );
+        let (across, down) = (x.fract(), y.fract());
+
+        let value = |x: u32, y: u32| self.values[(y * self.width + x) as usize];
+
+        let upper = value(left, top) * (1. - across) + value(right, top) * across;
+        let lower = value(left, bottom) * (1. - across) + value(right, bottom) * across;
+
+        upper * (1. - down) + lower * down
+    }
+}
+
+// Stretches the heightmap over the whole map and reads it at the centre of each hex, rescaled to
+// the -1 to 1 range the noise covers
+pub(crate) fn elevations(heightmap: &Heightmap, positions: &[Vec2]) -> Vec<f64> {
+    let (min, max) = positions.iter().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), position| (min.min(*position), max.max(*position)),
+    );
+
+    let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+
+    positions
+        .iter()
+        .map(|position| {
+            // Images run down the page, while the layout runs up it
+            let x = (position.x - min.x) / extent.x;
+            let y = (max.y - position.y) / extent.y;
+
+            heightmap.sample(x as f64, y as f64) * 2. - 1.
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams, TerrainType};
+
+    #[test]
+    fn heightmaps_cover_the_whole_map() {
+        // Rising from the sea on the left to mountains on the right
+        let heightmap = Heightmap::new(2, 1, vec![0., 1.]);
+        let terrain = terrain::generate_from_heightmap(
+            TerrainParams::new(30, 20, 1.2, 1).with_polar_band(0),
+            &heightmap,
+        );
+
+        let terrain = &terrain;
+        let column = |x: i32| {
+            (1..=20).map(move |y| terrain.get(terrain.grid().cell_to_hex([x, y])).unwrap())
+        };
+
+        assert!(column(1).all(|tile| tile == TerrainType::DeepOcean));
+        assert!(column(30).all(|tile| tile == TerrainType::Peaks));
+    }
+}
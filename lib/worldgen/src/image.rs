@@ -0,0 +1,260 @@
+use std::{collections::HashMap, io::Write};
+
+use hexx::{Hex, HexLayout, HexOrientation, Vec2};
+
+use crate::{palette::terrain_colour, terrain::GeneratedTerrain};
+
+const BORDER_COLOUR: [u8; 4] = [0, 0, 0, 255];
+const SETTLEMENT_COLOUR: [u8; 4] = [100, 50, 150, 255];
+
+// Both are measured as a share of the hex size, matching how the app draws them
+const BORDER_WIDTH: f32 = 0.13;
+const SETTLEMENT_SIZE: f32 = 1.;
+
+#[derive(Debug, Clone)]
+pub struct ImageParams {
+    pixels_per_hex: u32,
+    zones: Option<HashMap<Hex, usize>>,
+    settlements: Vec<Hex>,
+    tile_seam: bool,
+}
+
+impl ImageParams {
+    // Hexes are drawn `pixels_per_hex` across, measured corner to corner for flat-top hexes and
+    // side to side for pointy-top ones
+    pub fn new(pixels_per_hex: u32) -> Self {
+        Self {
+            pixels_per_hex: pixels_per_hex.max(2),
+            zones: None,
+            settlements: Vec::new(),
+            tile_seam: false,
+        }
+    }
+
+    // Draws a border between neighbouring hexes that belong to different zones
+    pub fn with_zone_borders(self, zones: impl IntoIterator<Item = (Hex, usize)>) -> Self {
+        Self {
+            zones: Some(zones.into_iter().collect()),
+            ..self
+        }
+    }
+
+    pub fn with_settlements(self, settlements: impl IntoIterator<Item = Hex>) -> Self {
+        Self {
+            settlements: settlements.into_iter().collect(),
+            ..self
+        }
+    }
+
+    // Crops wrapping maps to exactly one period along each wrapped axis, filling the ragged edges
+    // from the other side so copies of the image can be laid side by side without a seam
+    pub fn with_seam_tiling(self) -> Self {
+        Self {
+            tile_seam: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl MapImage {
+    pub(crate) fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // RGBA, row by row from the top left
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y * self.width + x) as usize * 4;
+        std::array::from_fn(|channel| self.pixels[index + channel])
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()
+    }
+}
+
+// Stretches the layout very slightly along an axis so a wrapped period of the map covers a whole
+// number of pixels
+fn snap_period(layout: &mut HexLayout, axis: usize, period: f32) -> f32 {
+    let snapped = period.round().max(1.);
+    layout.scale[axis] *= snapped / period;
+    snapped
+}
+
+pub fn render(
+    terrain: &GeneratedTerrain,
+    ImageParams {
+        pixels_per_hex,
+        zones,
+        settlements,
+        tile_seam,
+    }: ImageParams,
+) -> MapImage {
+    let orientation = terrain.orientation();
+    let topology = terrain.topology();
+
+    let mut layout = HexLayout::new(orientation).with_hex_size(pixels_per_hex as f32 / 2.);
+    let rect = layout.rect_size();
+
+    // Flat-top columns and pointy-top rows overlap by a quarter of a hex
+    let spacing = match orientation {
+        HexOrientation::Flat => Vec2::new(rect.x * 0.75, rect.y),
+        HexOrientation::Pointy => Vec2::new(rect.x, rect.y * 0.75),
+    };
+
+    let tile_x = tile_seam && topology.wraps_x();
+    let tile_y = tile_seam && topology.wraps_y();
+
+    let period_x = tile_x.then(|| snap_period(&mut layout, 0, spacing.x * terrain.width() as f32));
+    let period_y = tile_y.then(|| snap_period(&mut layout, 1, spacing.y * terrain.height() as f32));
+
+    let rect = layout.rect_size();
+
+    let (min, max) = terrain.tiles().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), (hex, _)| {
+            let position = layout.hex_to_world_pos(hex);
+            (min.min(position), max.max(position))
+        },
+    );
+
+    let min = min - rect / 2.;
+    let max = max + rect / 2.;
+
+    let width = period_x.unwrap_or((max.x - min.x).ceil()) as u32;
+    let height = period_y.unwrap_or((max.y - min.y).ceil()) as u32;
+
+    // Without tiling, hexes beyond the seam are left blank rather than drawn from the other side
+    let locate = |hex: Hex| {
+        if tile_seam || terrain.grid().hex_to_index(hex).is_some() {
+            Some(terrain.wrap(hex))
+        } else {
+            None
+        }
+    };
+
+    let zone = |hex: Hex| {
+        zones
+            .as_ref()
+            .and_then(|zones| zones.get(&terrain.wrap(hex)))
+    };
+
+    let settlements = settlements
+        .into_iter()
+        .map(|hex| terrain.wrap(hex))
+        .collect::<Vec<_>>();
+
+    let border_width = (layout.scale.min_element() * BORDER_WIDTH).max(1.) / 2.;
+    let settlement_size = layout.scale.min_element() * SETTLEMENT_SIZE / 2.;
+
+    let mut pixels = vec![0; (width * height * 4) as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            // Images run down the page, while the layout runs up it like the app
+            let position = Vec2::new(min.x + x as f32 + 0.5, max.y - y as f32 - 0.5);
+
+            let hex = layout.world_pos_to_hex(position);
+
+            let Some(tile) = locate(hex).and_then(|hex| terrain.get(hex)) else {
+                continue;
+            };
+
+            let centre = layout.hex_to_world_pos(hex);
+            let offset = position - centre;
+
+            let mut colour = terrain_colour(tile, terrain.biome(hex));
+
+            if zones.is_some() {
+                // The nearest edge lies towards the neighbour furthest along the offset
+                let (neighbour, distance) = hex
+                    .all_neighbors()
+                    .into_iter()
+                    .map(|neighbour| {
+                        let towards = layout.hex_to_world_pos(neighbour) - centre;
+                        let along = offset.dot(towards) / towards.length();
+                        (neighbour, towards.length() / 2. - along)
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .unwrap();
+
+                let crosses_zones = zone(hex)
+                    .zip(zone(neighbour))
+                    .is_some_and(|(this, other)| this != other);
+
+                if distance < border_width && crosses_zones {
+                    colour = BORDER_COLOUR;
+                }
+            }
+
+            if offset.abs().max_element() < settlement_size
+                && settlements.contains(&terrain.wrap(hex))
+            {
+                colour = SETTLEMENT_COLOUR;
+            }
+
+            let index = ((y * width + x) * 4) as usize;
+            pixels[index..index + 4].copy_from_slice(&colour);
+        }
+    }
+
+    MapImage::new(width, height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams};
+
+    #[test]
+    fn tiled_images_have_no_gaps_at_the_seam() {
+        let terrain = terrain::generate(TerrainParams::new(40, 24, 1.2, 7));
+
+        let image = render(&terrain, ImageParams::new(8).with_seam_tiling());
+
+        // The top and bottom of a cylinder are still ragged, but the sides should be solid
+        let rows = image.height() / 4..image.height() * 3 / 4;
+
+        assert_eq!(image.width(), 40 * 6);
+        assert!(rows.clone().all(|y| image.pixel(0, y)[3] == 255));
+        assert!(
+            rows.clone()
+                .all(|y| image.pixel(image.width() - 1, y)[3] == 255)
+        );
+
+        let untiled = render(&terrain, ImageParams::new(8));
+
+        assert!(rows.clone().any(|y| untiled.pixel(0, y)[3] == 0));
+    }
+}
@@ -1,4 +1,25 @@
+#[cfg(feature = "geojson")]
+pub mod geojson;
+pub mod heightmap;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod names;
+pub mod nations;
+pub mod palette;
+pub mod pathfinding;
+pub mod rivers;
+pub mod roads;
+#[cfg(feature = "serde")]
+pub mod save;
 pub mod settlements;
+pub mod sphere;
 pub mod terrain;
+pub mod territories;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+#[cfg(feature = "serde")]
+pub mod world;
 
-mod cylinder;
+mod grid;
+mod queue;
+mod sampler;
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// Each letter is chosen from the letters that followed the previous few in the training names
+const ORDER: usize = 3;
+
+// Mark the ends of a name, so names start and finish the way the training names do
+const START: char = '\u{2}';
+const END: char = '\u{3}';
+
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 24;
+
+// How many names in a row can be thrown away before giving up on finding a new one
+const MAX_ATTEMPTS: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+struct Choices {
+    total: u32,
+    letters: Vec<(char, u32)>,
+}
+
+impl Choices {
+    fn add(&mut self, letter: char) {
+        self.total += 1;
+
+        match self.letters.iter_mut().find(|(other, _)| *other == letter) {
+            Some((_, count)) => *count += 1,
+            None => self.letters.push((letter, 1)),
+        }
+    }
+
+    fn pick(&self, rng: &mut impl Rng) -> char {
+        let mut pick = rng.random_range(0..self.total);
+
+        for (letter, count) in &self.letters {
+            if pick < *count {
+                return *letter;
+            }
+
+            pick -= count;
+        }
+
+        unreachable!()
+    }
+}
+
+// Makes up names that sound like a list of real ones, using a Markov chain over their letters
+#[derive(Debug, Clone)]
+pub struct NameGenerator {
+    chain: HashMap<[char; ORDER], Choices>,
+    sources: HashSet<String>,
+    allow_sources: bool,
+}
+
+impl NameGenerator {
+    pub fn new<Name: AsRef<str>>(names: impl IntoIterator<Item = Name>) -> Self {
+        let mut chain = HashMap::<_, Choices>::new();
+        let mut sources = HashSet::new();
+
+        for name in names {
+            let name = name.as_ref().trim();
+
+            if name.is_empty() {
+                continue;
+            }
+
+            let mut context = [START; ORDER];
+
+            for letter in name.chars().chain([END]) {
+                chain.entry(context).or_default().add(letter);
+
+                context.rotate_left(1);
+                context[ORDER - 1] = letter;
+            }
+
+            sources.insert(name.to_owned());
+        }
+
+        Self {
+            chain,
+            sources,
+            allow_sources: false,
+        }
+    }
+
+    // Lets the generator come up with names that were in the training list, which are skipped
+    // otherwise
+    pub fn with_source_names(self) -> Self {
+        Self {
+            allow_sources: true,
+            ..self
+        }
+    }
+
+    fn generate(&self, rng: &mut impl Rng) -> Option<String> {
+        let mut context = [START; ORDER];
+        let mut name = String::new();
+
+        for _ in 0..=MAX_LENGTH {
+            let letter = self.chain.get(&context)?.pick(rng);
+
+            if letter == END {
+                return Some(name);
+            }
+
+            name.push(letter);
+
+            context.rotate_left(1);
+            context[ORDER - 1] = letter;
+        }
+
+        None
+    }
+
+    // An endless supply of different names, the same for the same seed. It only runs dry if the
+    // training names are too few to make anything new from.
+    pub fn names(&self, seed: u64) -> Names<'_> {
+        Names {
+            generator: self,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            used: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Names<'a> {
+    generator: &'a NameGenerator,
+    rng: ChaCha8Rng,
+    used: HashSet<String>,
+}
+
+impl Iterator for Names<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for _ in 0..MAX_ATTEMPTS {
+            let Some(name) = self.generator.generate(&mut self.rng) else {
+                continue;
+            };
+
+            if name.chars().count() < MIN_LENGTH
+                || (!self.generator.allow_sources && self.generator.sources.contains(&name))
+            {
+                continue;
+            }
+
+            if self.used.insert(name.clone()) {
+                return Some(name);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: &str = include_str!("../../../assets/settlement_names.csv");
+
+    fn sources() -> impl Iterator<Item = &'static str> {
+        SOURCES.lines().skip(1)
+    }
+
+    #[test]
+    fn names_are_new_unique_and_unlimited() {
+        let generator = NameGenerator::new(sources());
+
+        // Far more names than there are in the list
+        let names = generator.names(5).take(10_000).collect::<Vec<_>>();
+
+        assert_eq!(names.len(), 10_000);
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len());
+        assert!(names.iter().all(|name| !generator.sources.contains(name)));
+
+        assert!(
+            generator
+                .names(5)
+                .take(100)
+                .eq(names.iter().take(100).cloned())
+        );
+    }
+
+    #[test]
+    fn source_names_can_be_allowed() {
+        let names = ["Ash", "Ashby", "Ashford"];
+
+        assert_eq!(NameGenerator::new(names).names(1).next(), None);
+
+        let mut allowed = NameGenerator::new(names)
+            .with_source_names()
+            .names(1)
+            .collect::<Vec<_>>();
+        allowed.sort();
+
+        assert_eq!(allowed, names);
+    }
+}
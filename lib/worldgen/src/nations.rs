@@ -0,0 +1,256 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+};
+
+use crate::{
+    pathfinding::TravelCost, settlements::Settlement, terrain::GeneratedTerrain,
+    territories::Territories,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct NationParams {
+    reach: usize,
+}
+
+impl NationParams {
+    // Zones join a nation whose capital they can reach for at most `reach`, and any settlement
+    // further than that from every capital becomes a capital itself
+    pub fn new(reach: usize) -> Self {
+        Self { reach }
+    }
+}
+
+impl Default for NationParams {
+    fn default() -> Self {
+        Self::new(80)
+    }
+}
+
+// A group of neighbouring zones, given by the indices of their settlements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nation {
+    pub capital: usize,
+    pub zones: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Claim {
+    cost: usize,
+    nation: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Nations {
+    nations: Vec<Nation>,
+    claims: Vec<Option<Claim>>,
+}
+
+impl Nations {
+    pub fn nations(&self) -> &[Nation] {
+        &self.nations
+    }
+
+    // The nation the zone of the settlement at this index belongs to
+    pub fn nation(&self, zone: usize) -> Option<usize> {
+        self.claims
+            .get(zone)
+            .copied()
+            .flatten()
+            .map(|claim| claim.nation)
+    }
+}
+
+// The cheapest way from each zone's seat into each neighbouring zone's seat. Crossing the border
+// itself costs whichever side is harder going in full, so mountain ridges and stretches of sea
+// between zones keep them apart.
+fn crossings(
+    terrain: &GeneratedTerrain,
+    territories: &Territories,
+    cost: &impl TravelCost,
+    zones: usize,
+) -> Vec<Vec<(usize, usize)>> {
+    let entry = |hex| cost.cost(terrain.get(hex)?, terrain.biome(hex));
+
+    let mut cheapest = BTreeMap::<(usize, usize), usize>::new();
+
+    for (hex, here) in territories.iter() {
+        let Some(inside) = entry(hex) else {
+            continue;
+        };
+
+        for neighbour in terrain.neighbors(hex) {
+            let Some(there) = territories.get(neighbour) else {
+                continue;
+            };
+
+            if there.zone <= here.zone {
+                continue;
+            }
+
+            let Some(outside) = entry(neighbour) else {
+                continue;
+            };
+
+            let crossing = here.cost + there.cost + inside.max(outside);
+
+            cheapest
+                .entry((here.zone, there.zone))
+                .and_modify(|existing| *existing = (*existing).min(crossing))
+                .or_insert(crossing);
+        }
+    }
+
+    let mut links = vec![Vec::new(); zones];
+
+    for ((a, b), crossing) in cheapest {
+        links[a].push((b, crossing));
+        links[b].push((a, crossing));
+    }
+
+    links
+}
+
+// Groups the zones around settlements into nations. The largest settlements become capitals,
+// taking in every zone they can reach more cheaply than any other capital. Ties go to the nation
+// founded first.
+pub fn generate(
+    terrain: &GeneratedTerrain,
+    settlements: &[Settlement],
+    territories: &Territories,
+    cost: impl TravelCost,
+    NationParams { reach }: NationParams,
+) -> Nations {
+    let links = crossings(terrain, territories, &cost, settlements.len());
+
+    let mut order = (0..settlements.len()).collect::<Vec<_>>();
+    order.sort_by_key(|zone| (Reverse(settlements[*zone].population), *zone));
+
+    let mut capitals = Vec::new();
+    let mut claims = vec![None; settlements.len()];
+
+    for zone in order {
+        if claims[zone].is_some_and(|claim: Claim| claim.cost <= reach) {
+            continue;
+        }
+
+        let nation = capitals.len();
+        capitals.push(zone);
+
+        // Spread the new nation out until it meets zones that are closer to an older capital
+        let mut queue = BinaryHeap::new();
+
+        claims[zone] = Some(Claim { cost: 0, nation });
+        queue.push(Reverse((0, zone)));
+
+        while let Some(Reverse((cost, zone))) = queue.pop() {
+            if claims[zone] != Some(Claim { cost, nation }) {
+                continue;
+            }
+
+            for (neighbour, crossing) in &links[zone] {
+                let claim = Claim {
+                    cost: cost + crossing,
+                    nation,
+                };
+
+                if claims[*neighbour].is_none_or(|existing| claim < existing) {
+                    claims[*neighbour] = Some(claim);
+                    queue.push(Reverse((claim.cost, *neighbour)));
+                }
+            }
+        }
+    }
+
+    let mut nations = capitals
+        .into_iter()
+        .map(|capital| Nation {
+            capital,
+            zones: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    for (zone, claim) in claims.iter().enumerate() {
+        if let Some(claim) = claim {
+            nations[claim.nation].zones.push(zone);
+        }
+    }
+
+    Nations { nations, claims }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        pathfinding::ZoneCost,
+        settlements::{self, SettlementParams},
+        terrain::{self, TerrainParams},
+        territories,
+    };
+
+    #[test]
+    fn zones_join_the_cheapest_capital_within_reach() {
+        let terrain = terrain::generate(TerrainParams::new(80, 48, 1.2, 3));
+        let settlements =
+            settlements::generate(&terrain, SettlementParams::new(5)).collect::<Vec<_>>();
+        let seats = settlements
+            .iter()
+            .map(|settlement| settlement.hex)
+            .collect::<Vec<_>>();
+
+        let territories = territories::generate(&terrain, &seats, ZoneCost);
+        let params = NationParams::default();
+        let nations = generate(&terrain, &settlements, &territories, ZoneCost, params);
+
+        assert!(nations.nations().len() > 1);
+        assert!(nations.nations().len() < settlements.len());
+
+        // The largest settlement is always a capital
+        let largest = (0..settlements.len())
+            .max_by_key(|zone| (settlements[*zone].population, Reverse(*zone)))
+            .unwrap();
+        assert_eq!(nations.nations()[0].capital, largest);
+
+        let links = crossings(&terrain, &territories, &ZoneCost, settlements.len());
+
+        for (index, nation) in nations.nations().iter().enumerate() {
+            assert!(nation.zones.contains(&nation.capital));
+            assert_eq!(nations.claims[nation.capital].unwrap().cost, 0);
+
+            for zone in &nation.zones {
+                let claim = nations.claims[*zone].unwrap();
+
+                assert_eq!(nations.nation(*zone), Some(index));
+                assert!(claim.cost <= params.reach);
+
+                if *zone == nation.capital {
+                    continue;
+                }
+
+                let best = links[*zone]
+                    .iter()
+                    .map(|(neighbour, crossing)| {
+                        let from = nations.claims[*neighbour].unwrap();
+                        Claim {
+                            cost: from.cost + crossing,
+                            nation: from.nation,
+                        }
+                    })
+                    .min();
+
+                assert_eq!(Some(claim), best);
+            }
+        }
+
+        assert_eq!(
+            nations
+                .nations()
+                .iter()
+                .map(|nation| nation.zones.len())
+                .sum::<usize>(),
+            settlements.len()
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::terrain::{Biome, TerrainType};
+
+pub fn biome_colour(biome: Biome) -> [u8; 4] {
+    match biome {
+        Biome::Desert => [220, 200, 130, 255],
+        Biome::Grassland => [110, 150, 70, 255],
+        Biome::Forest => [50, 110, 50, 255],
+        Biome::Jungle => [30, 90, 40, 255],
+        Biome::Taiga => [60, 100, 80, 255],
+        Biome::Tundra => [150, 160, 140, 255],
+        Biome::Ice => [235, 240, 250, 255],
+        Biome::Swamp => [70, 90, 60, 255],
+    }
+}
+
+fn blend(from: [u8; 4], to: [u8; 4], amount: f32) -> [u8; 4] {
+    std::array::from_fn(|index| {
+        (from[index] as f32 + (to[index] as f32 - from[index] as f32) * amount) as u8
+    })
+}
+
+pub fn terrain_colour(terrain: TerrainType, biome: Option<Biome>) -> [u8; 4] {
+    let base = match terrain {
+        TerrainType::DeepOcean => [6, 58, 127, 255],
+        TerrainType::ShallowOcean => [14, 112, 192, 255],
+        TerrainType::Coast => [25, 150, 230, 255],
+        TerrainType::Lake => [45, 125, 175, 255],
+        TerrainType::Beach => [210, 170, 110, 255],
+        TerrainType::Plains => [70, 120, 60, 255],
+        TerrainType::Hills => [110, 140, 100, 255],
+        TerrainType::LowMountains => [150, 150, 150, 255],
+        TerrainType::HighMountains => [220, 220, 200, 255],
+        TerrainType::Peaks => [250, 250, 250, 255],
+        TerrainType::SeaIce => [200, 225, 240, 255],
+        TerrainType::Glacier => [240, 245, 255, 255],
+    };
+
+    match (terrain, biome) {
+        (_, Some(Biome::Ice)) => biome_colour(Biome::Ice),
+        (TerrainType::Plains, Some(biome)) => biome_colour(biome),
+        (TerrainType::Hills, Some(biome)) => blend(biome_colour(biome), base, 0.4),
+        (TerrainType::LowMountains, Some(biome)) => blend(base, biome_colour(biome), 0.2),
+        _ => base,
+    }
+}
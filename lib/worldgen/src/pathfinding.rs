@@ -0,0 +1,285 @@
+use std::collections::BinaryHeap;
+
+use hexx::Hex;
+
+use crate::{
+    grid::HexGrid,
+    queue::Lowest,
+    terrain::{Biome, GeneratedTerrain, TerrainType},
+};
+
+fn biome_zone_cost(biome: Biome) -> usize {
+    match biome {
+        Biome::Desert => 2,
+        Biome::Grassland => 0,
+        Biome::Forest => 1,
+        Biome::Jungle => 3,
+        Biome::Taiga => 2,
+        Biome::Tundra => 2,
+        Biome::Ice => 10,
+        Biome::Swamp => 4,
+    }
+}
+
+// How hard a hex is to cross, which is also how the zones around settlements spread
+pub fn terrain_zone_cost(terrain: TerrainType, biome: Option<Biome>) -> usize {
+    let base = match terrain {
+        TerrainType::DeepOcean => 500,
+        TerrainType::ShallowOcean => 100,
+        TerrainType::Coast => 50,
+        TerrainType::Lake => 80,
+        TerrainType::Beach => 2,
+        TerrainType::Plains => 2,
+        TerrainType::Hills => 3,
+        TerrainType::LowMountains => 100,
+        TerrainType::HighMountains => 500,
+        TerrainType::Peaks => 1000,
+        TerrainType::SeaIce => 400,
+        TerrainType::Glacier => 800,
+    };
+
+    base + biome.map_or(0, biome_zone_cost)
+}
+
+// The cost of stepping onto a hex, or `None` if it can't be entered at all
+pub trait TravelCost {
+    fn cost(&self, terrain: TerrainType, biome: Option<Biome>) -> Option<usize>;
+}
+
+impl<F> TravelCost for F
+where
+    F: Fn(TerrainType, Option<Biome>) -> Option<usize>,
+{
+    fn cost(&self, terrain: TerrainType, biome: Option<Biome>) -> Option<usize> {
+        self(terrain, biome)
+    }
+}
+
+// Costs every hex with `terrain_zone_cost`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZoneCost;
+
+impl TravelCost for ZoneCost {
+    fn cost(&self, terrain: TerrainType, biome: Option<Biome>) -> Option<usize> {
+        Some(terrain_zone_cost(terrain, biome))
+    }
+}
+
+// A route from its start to its end, with each hex wrapped onto the map. Neighbouring hexes in the
+// route may be on opposite sides of a seam.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub hexes: Vec<Hex>,
+    // The cost of every hex entered, not counting the start
+    pub cost: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    cost: usize,
+    previous: usize,
+}
+
+// The cheapest routes from one hex to every hex that can be reached from it
+#[derive(Debug, Clone)]
+pub struct Distances {
+    grid: HexGrid,
+    steps: Vec<Option<Step>>,
+}
+
+impl Distances {
+    pub fn cost(&self, hex: Hex) -> Option<usize> {
+        self.grid
+            .hex_to_index(self.grid.wrap(hex))
+            .and_then(|index| self.steps[index])
+            .map(|step| step.cost)
+    }
+
+    pub fn path(&self, hex: Hex) -> Option<Path> {
+        let end = self.grid.hex_to_index(self.grid.wrap(hex))?;
+        trace(self.grid, &self.steps, end)
+    }
+}
+
+pub(crate) fn entry_costs(
+    terrain: &GeneratedTerrain,
+    cost: &impl TravelCost,
+) -> Vec<Option<usize>> {
+    terrain
+        .tiles()
+        .zip(terrain.biomes())
+        .map(|((_, tile), (_, biome))| cost.cost(tile, biome))
+        .collect()
+}
+
+fn trace(grid: HexGrid, steps: &[Option<Step>], end: usize) -> Option<Path> {
+    let cost = steps[end]?.cost;
+    let mut hexes = vec![grid.index_to_hex(end)];
+    let mut current = end;
+
+    while let Some(step) = steps[current].filter(|step| step.previous != current) {
+        current = step.previous;
+        hexes.push(grid.index_to_hex(current));
+    }
+
+    hexes.reverse();
+
+    Some(Path { hexes, cost })
+}
+
+// Expands outwards from the start in order of cost plus the estimate of what's left, stopping once
+// the goal is reached. The start is recorded as its own previous step.
+fn search(
+    grid: HexGrid,
+    costs: &[Option<usize>],
+    start: usize,
+    goal: Option<usize>,
+    estimate: impl Fn(usize) -> usize,
+) -> Vec<Option<Step>> {
+    let mut steps = vec![None; costs.len()];
+    let mut queue = BinaryHeap::new();
+
+    steps[start] = Some(Step {
+        cost: 0,
+        previous: start,
+    });
+    queue.push(Lowest::new(estimate(start) as f64, start));
+
+    while let Some(Lowest { value, index }) = queue.pop() {
+        let cost = steps[index].unwrap().cost;
+
+        // Skip entries left behind when a cheaper route was found
+        if value > (cost + estimate(index)) as f64 {
+            continue;
+        }
+
+        if goal == Some(index) {
+            break;
+        }
+
+        for neighbour in grid.neighbours(index) {
+            let Some(entry) = costs[neighbour] else {
+                continue;
+            };
+
+            let next = cost + entry;
+
+            if steps[neighbour].is_none_or(|step| next < step.cost) {
+                steps[neighbour] = Some(Step {
+                    cost: next,
+                    previous: index,
+                });
+                queue.push(Lowest::new((next + estimate(neighbour)) as f64, neighbour));
+            }
+        }
+    }
+
+    steps
+}
+
+// Finds the cheapest route between two cells with the given entry costs, guided towards the end by
+// the fewest steps left times the cheapest cell on the map
+pub(crate) fn route(
+    grid: HexGrid,
+    costs: &[Option<usize>],
+    start: usize,
+    goal: usize,
+) -> Option<Path> {
+    let cheapest = costs.iter().flatten().min().copied().unwrap_or(0);
+
+    let end = grid.index_to_hex(goal);
+    let steps = search(grid, costs, start, Some(goal), |index| {
+        grid.distance(grid.index_to_hex(index), end) as usize * cheapest
+    });
+
+    trace(grid, &steps, goal)
+}
+
+// Finds the cheapest route between two hexes
+pub fn a_star(
+    terrain: &GeneratedTerrain,
+    from: Hex,
+    to: Hex,
+    cost: impl TravelCost,
+) -> Option<Path> {
+    let grid = terrain.grid();
+    let start = grid.hex_to_index(grid.wrap(from))?;
+    let goal = grid.hex_to_index(grid.wrap(to))?;
+
+    route(grid, &entry_costs(terrain, &cost), start, goal)
+}
+
+// Finds the cheapest routes from a hex to everywhere reachable from it
+pub fn dijkstra(terrain: &GeneratedTerrain, from: Hex, cost: impl TravelCost) -> Option<Distances> {
+    let grid = terrain.grid();
+    let start = grid.hex_to_index(grid.wrap(from))?;
+
+    let costs = entry_costs(terrain, &cost);
+    let steps = search(grid, &costs, start, None, |_| 0);
+
+    Some(Distances { grid, steps })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams, Topology};
+
+    fn even(_: TerrainType, _: Option<Biome>) -> Option<usize> {
+        Some(1)
+    }
+
+    #[test]
+    fn routes_cross_the_seam() {
+        let terrain = terrain::generate(TerrainParams::new(20, 12, 1.2, 3));
+        let grid = terrain.grid();
+
+        let (west, east) = (grid.cell_to_hex([2, 6]), grid.cell_to_hex([19, 6]));
+
+        let path = a_star(&terrain, west, east, even).unwrap();
+
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.hexes.first(), Some(&west));
+        assert_eq!(path.hexes.last(), Some(&east));
+        assert!(
+            path.hexes
+                .windows(2)
+                .all(|pair| terrain.neighbors(pair[0]).any(|hex| hex == pair[1]))
+        );
+
+        let distances = dijkstra(&terrain, west, even).unwrap();
+
+        assert_eq!(distances.cost(east), Some(3));
+        assert_eq!(distances.path(east).unwrap().cost, 3);
+
+        // Without a seam the only way round is the long way
+        let flat =
+            terrain::generate(TerrainParams::new(20, 12, 1.2, 3).with_topology(Topology::Flat));
+
+        assert_eq!(a_star(&flat, west, east, even).unwrap().cost, 17);
+    }
+
+    #[test]
+    fn a_star_agrees_with_dijkstra() {
+        let terrain = terrain::generate(TerrainParams::new(40, 24, 1.2, 11));
+        let grid = terrain.grid();
+
+        let from = grid.cell_to_hex([3, 12]);
+        let distances = dijkstra(&terrain, from, ZoneCost).unwrap();
+
+        for to in [[38, 4], [20, 20], [1, 1], [40, 24]] {
+            let to = grid.cell_to_hex(to);
+            let path = a_star(&terrain, from, to, ZoneCost).unwrap();
+
+            assert_eq!(Some(path.cost), distances.cost(to));
+            assert_eq!(
+                path.cost,
+                path.hexes[1..]
+                    .iter()
+                    .map(|hex| terrain_zone_cost(terrain.get(*hex).unwrap(), terrain.biome(*hex)))
+                    .sum::<usize>()
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::cmp::Ordering;
+
+// An entry for a `BinaryHeap` that pops the lowest value first, breaking ties on the index so
+// that the order is fully deterministic
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Lowest {
+    pub value: f64,
+    pub index: usize,
+}
+
+impl Lowest {
+    pub fn new(value: f64, index: usize) -> Self {
+        Self { value, index }
+    }
+}
+
+impl Eq for Lowest {}
+
+impl Ord for Lowest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed to turn the max-heap into a min-heap
+        other
+            .value
+            .total_cmp(&self.value)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Lowest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
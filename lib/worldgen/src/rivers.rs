@@ -0,0 +1,301 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use hexx::{EdgeDirection, GridEdge, Hex};
+
+use crate::{queue::Lowest, terrain::GeneratedTerrain};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RiverParams {
+    flow_threshold: f64,
+}
+
+impl RiverParams {
+    pub fn new(flow_threshold: f64) -> Self {
+        Self { flow_threshold }
+    }
+
+    pub fn flow_threshold(&self) -> f64 {
+        self.flow_threshold
+    }
+}
+
+impl Default for RiverParams {
+    fn default() -> Self {
+        Self::new(40.)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RiverSegment {
+    pub edge: GridEdge,
+    pub flow: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedRivers {
+    segments: Vec<RiverSegment>,
+}
+
+impl GeneratedRivers {
+    pub fn segments(&self) -> impl Iterator<Item = RiverSegment> {
+        self.segments.iter().copied()
+    }
+}
+
+// A vertex of the hex grid, identified by the three (wrapped) hexes that meet at it
+type Vertex = [Hex; 3];
+
+struct VertexInfo {
+    hexes: Vertex,
+    elevation: f64,
+    rainfall: f64,
+    is_water: bool,
+    neighbours: Vec<usize>,
+}
+
+fn to_vertex(mut hexes: Vertex) -> Vertex {
+    hexes.sort_by_key(|hex| (hex.x, hex.y));
+    hexes
+}
+
+fn direction_between(terrain: &GeneratedTerrain, from: Hex, to: Hex) -> Option<EdgeDirection> {
+    EdgeDirection::ALL_DIRECTIONS
+        .into_iter()
+        .find(|direction| terrain.wrap(from.neighbor(*direction)) == to)
+}
+
+fn collect_vertices(terrain: &GeneratedTerrain) -> Vec<VertexInfo> {
+    let mut indices = HashMap::<Vertex, usize>::new();
+    let mut vertices = Vec::new();
+
+    for (hex, _) in terrain.tiles() {
+        for index in 0..6 {
+            let a = terrain.wrap(hex.neighbor(EdgeDirection::ALL_DIRECTIONS[index]));
+            let b = terrain.wrap(hex.neighbor(EdgeDirection::ALL_DIRECTIONS[(index + 1) % 6]));
+            let hexes = to_vertex([hex, a, b]);
+
+            if indices.contains_key(&hexes) {
+                continue;
+            }
+
+            // Vertices along the top and bottom edges of the map are not part of the graph
+            let Some(tiles) = hexes
+                .into_iter()
+                .map(|hex| terrain.get(hex))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            let elevation = hexes
+                .iter()
+                .filter_map(|hex| terrain.elevation(*hex))
+                .sum::<f64>();
+
+            let rainfall = hexes
+                .iter()
+                .filter_map(|hex| terrain.climate(*hex))
+                .map(|climate| climate.moisture)
+                .sum::<f64>();
+
+            indices.insert(hexes, vertices.len());
+            vertices.push(VertexInfo {
+                hexes,
+                elevation: elevation / 3.,
+                rainfall: rainfall / 3.,
+                is_water: tiles.iter().any(|tile| !tile.is_land()),
+                neighbours: Vec::new(),
+            });
+        }
+    }
+
+    let neighbours = vertices
+        .iter()
+        .map(|vertex| {
+            let hexes = vertex.hexes;
+
+            [(0, 1, 2), (0, 2, 1), (1, 2, 0)]
+                .into_iter()
+                .filter_map(|(first, second, third)| {
+                    let (first, second, third) = (hexes[first], hexes[second], hexes[third]);
+
+                    // The vertex at the other end of the edge between two hexes is the one
+                    // formed with their other shared neighbour
+                    let other = first
+                        .all_neighbors()
+                        .into_iter()
+                        .map(|hex| terrain.wrap(hex))
+                        .find(|hex| {
+                            *hex != third && direction_between(terrain, second, *hex).is_some()
+                        })?;
+
+                    indices.get(&to_vertex([first, second, other])).copied()
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    for (vertex, neighbours) in vertices.iter_mut().zip(neighbours) {
+        vertex.neighbours = neighbours;
+    }
+
+    vertices
+}
+
+fn edge_between(
+    terrain: &GeneratedTerrain,
+    from: &VertexInfo,
+    to: &VertexInfo,
+) -> Option<GridEdge> {
+    let mut shared = from.hexes.into_iter().filter(|hex| to.hexes.contains(hex));
+    let (origin, other) = (shared.next()?, shared.next()?);
+
+    direction_between(terrain, origin, other).map(|direction| GridEdge { origin, direction })
+}
+
+// Where the water falling on each vertex runs to, and how much passes through it
+struct Drainage {
+    vertices: Vec<VertexInfo>,
+    downstream: Vec<Option<usize>>,
+    flow: Vec<f64>,
+    // Every vertex reached, each after the vertex it drains into
+    order: Vec<usize>,
+}
+
+fn drain(terrain: &GeneratedTerrain) -> Drainage {
+    let vertices = collect_vertices(terrain);
+
+    let mut downstream = vec![None; vertices.len()];
+    let mut visited = vec![false; vertices.len()];
+    let mut order = Vec::with_capacity(vertices.len());
+    let mut queue = BinaryHeap::new();
+
+    // Flood inwards from the sea and lakes, lowest first, so every land vertex drains towards the
+    // point it was reached from. Depressions are spilled over rather than trapping water, which
+    // guarantees every river ends in the ocean or an inland body of water. Land along the top and
+    // bottom edges of the map drains inwards like any other.
+    for (index, vertex) in vertices.iter().enumerate() {
+        if vertex.is_water {
+            visited[index] = true;
+            queue.push(Lowest::new(vertex.elevation, index));
+        }
+    }
+
+    while let Some(Lowest {
+        value: elevation,
+        index,
+    }) = queue.pop()
+    {
+        order.push(index);
+
+        for neighbour in &vertices[index].neighbours {
+            if visited[*neighbour] {
+                continue;
+            }
+
+            visited[*neighbour] = true;
+            downstream[*neighbour] = Some(index);
+            queue.push(Lowest::new(
+                vertices[*neighbour].elevation.max(elevation),
+                *neighbour,
+            ));
+        }
+    }
+
+    // Accumulate from the furthest upstream vertices back down to the sinks
+    let mut flow = vertices
+        .iter()
+        .map(|vertex| if vertex.is_water { 0. } else { vertex.rainfall })
+        .collect::<Vec<_>>();
+
+    for index in order.iter().rev() {
+        if let Some(next) = downstream[*index] {
+            flow[next] += flow[*index];
+        }
+    }
+
+    Drainage {
+        vertices,
+        downstream,
+        flow,
+        order,
+    }
+}
+
+pub fn generate(
+    terrain: &GeneratedTerrain,
+    RiverParams { flow_threshold }: RiverParams,
+) -> GeneratedRivers {
+    let Drainage {
+        vertices,
+        downstream,
+        flow,
+        order,
+    } = drain(terrain);
+
+    let segments = order
+        .iter()
+        .filter(|index| flow[**index] >= flow_threshold)
+        .filter_map(|index| {
+            let next = downstream[*index]?;
+            let edge = edge_between(terrain, &vertices[*index], &vertices[next])?;
+
+            Some(RiverSegment {
+                edge,
+                flow: flow[*index],
+            })
+        })
+        .collect();
+
+    GeneratedRivers { segments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams, Topology};
+
+    #[test]
+    fn rivers_run_downhill_into_water() {
+        // Without ice caps, so there's land along the top and bottom edges
+        let terrain =
+            terrain::generate(TerrainParams::new(80, 48, 1.2, 3).with_topology(Topology::Flat));
+        let drainage = drain(&terrain);
+
+        // Every drop of rain on land, not just what's enough to form a river, reaches water
+        for (index, vertex) in drainage.vertices.iter().enumerate() {
+            if vertex.is_water {
+                continue;
+            }
+
+            let mut current = index;
+
+            while let Some(next) = drainage.downstream[current] {
+                assert!(drainage.flow[next] >= drainage.flow[current]);
+                current = next;
+            }
+
+            // The mouth is a vertex touching the sea or a lake, not dry land at the map edge
+            assert!(
+                drainage.vertices[current]
+                    .hexes
+                    .iter()
+                    .filter_map(|hex| terrain.get(*hex))
+                    .any(|tile| !tile.is_land())
+            );
+        }
+    }
+
+    #[test]
+    fn rivers_cross_the_east_west_seam() {
+        // A seed with a river flowing over the seam
+        let terrain = terrain::generate(TerrainParams::new(80, 48, 1.2, 10));
+        let rivers = generate(&terrain, RiverParams::default());
+
+        assert!(rivers.segments().any(|segment| {
+            let neighbour = segment.edge.origin.neighbor(segment.edge.direction);
+            terrain.wrap(neighbour) != neighbour
+        }));
+    }
+}
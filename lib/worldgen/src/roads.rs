@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+
+use hexx::{EdgeDirection, GridEdge, Hex};
+
+use crate::{
+    grid::HexGrid,
+    pathfinding::{self, Path},
+    terrain::{Biome, GeneratedTerrain, TerrainType},
+};
+
+// Following an existing road is cheaper than breaking new ground anywhere, so later roads join
+// onto earlier ones rather than running alongside them
+const ROAD_COST: usize = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RoadParams {
+    max_length: u32,
+}
+
+impl RoadParams {
+    // Settlements are only linked when they're at most `max_length` hexes apart
+    pub fn new(max_length: u32) -> Self {
+        Self { max_length }
+    }
+}
+
+impl Default for RoadParams {
+    fn default() -> Self {
+        Self::new(30)
+    }
+}
+
+// How hard it is to build a road across a hex. Roads keep to open ground where they can, and
+// never cross water, peaks or glaciers.
+pub fn road_cost(terrain: TerrainType, biome: Option<Biome>) -> Option<usize> {
+    let base = match terrain {
+        TerrainType::DeepOcean => return None,
+        TerrainType::ShallowOcean => return None,
+        TerrainType::Coast => return None,
+        TerrainType::Lake => return None,
+        TerrainType::Beach => 5,
+        TerrainType::Plains => 3,
+        TerrainType::Hills => 6,
+        TerrainType::LowMountains => 20,
+        TerrainType::HighMountains => 50,
+        TerrainType::Peaks => return None,
+        TerrainType::SeaIce => return None,
+        TerrainType::Glacier => return None,
+    };
+
+    let biome = match biome {
+        Some(Biome::Desert) => 2,
+        Some(Biome::Grassland) => 0,
+        Some(Biome::Forest) => 2,
+        Some(Biome::Jungle) => 6,
+        Some(Biome::Taiga) => 2,
+        Some(Biome::Tundra) => 2,
+        Some(Biome::Ice) => 10,
+        Some(Biome::Swamp) => 6,
+        None => 0,
+    };
+
+    Some(base + biome)
+}
+
+// A road between two settlements, given by their indices
+#[derive(Debug, Clone)]
+pub struct Road {
+    pub from: usize,
+    pub to: usize,
+    pub path: Path,
+}
+
+// A stretch of road between the centres of two neighbouring hexes
+#[derive(Debug, Clone, Copy)]
+pub struct RoadSegment {
+    pub edge: GridEdge,
+    // How many roads share the stretch
+    pub traffic: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedRoads {
+    grid: HexGrid,
+    roads: Vec<Road>,
+    segments: Vec<RoadSegment>,
+    on_road: Vec<bool>,
+}
+
+impl GeneratedRoads {
+    pub fn roads(&self) -> &[Road] {
+        &self.roads
+    }
+
+    // Every stretch of road, with those shared between several roads only appearing once
+    pub fn segments(&self) -> impl Iterator<Item = RoadSegment> {
+        self.segments.iter().copied()
+    }
+
+    pub fn contains(&self, hex: Hex) -> bool {
+        self.grid
+            .hex_to_index(self.grid.wrap(hex))
+            .is_some_and(|index| self.on_road[index])
+    }
+}
+
+// Links two settlements unless some third settlement is closer to both of them than they are to
+// each other. This is the relative neighbourhood graph, which joins everything the minimum
+// spanning tree does while adding the odd shortcut around loops.
+fn links(grid: HexGrid, settlements: &[Hex], max_length: u32) -> Vec<(u32, usize, usize)> {
+    let distances = settlements
+        .iter()
+        .map(|a| {
+            settlements
+                .iter()
+                .map(|b| grid.distance(*a, *b))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut links = Vec::new();
+
+    for a in 0..settlements.len() {
+        for b in a + 1..settlements.len() {
+            let length = distances[a][b];
+
+            if length > max_length {
+                continue;
+            }
+
+            let blocked = (0..settlements.len())
+                .filter(|c| *c != a && *c != b)
+                .any(|c| distances[a][c].max(distances[b][c]) < length);
+
+            if !blocked {
+                links.push((length, a, b));
+            }
+        }
+    }
+
+    links.sort();
+    links
+}
+
+// The direction to step from one hex to reach a neighbour, which may be across a seam
+fn direction(grid: HexGrid, from: Hex, to: Hex) -> Option<EdgeDirection> {
+    EdgeDirection::ALL_DIRECTIONS
+        .into_iter()
+        .find(|direction| grid.wrap(from.neighbor(*direction)) == to)
+}
+
+// Links nearby settlements and routes a road along the cheapest way between each pair, shortest
+// links first. Settlements with no way through to each other are left unlinked.
+pub fn generate(
+    terrain: &GeneratedTerrain,
+    settlements: &[Hex],
+    RoadParams { max_length }: RoadParams,
+) -> GeneratedRoads {
+    let grid = terrain.grid();
+    let mut costs = pathfinding::entry_costs(terrain, &road_cost);
+
+    let mut roads = Vec::new();
+    let mut traffic = BTreeMap::<(usize, usize), usize>::new();
+    let mut on_road = vec![false; grid.len()];
+
+    for (_, from, to) in links(grid, settlements, max_length) {
+        let (Some(start), Some(goal)) = (
+            grid.hex_to_index(grid.wrap(settlements[from])),
+            grid.hex_to_index(grid.wrap(settlements[to])),
+        ) else {
+            continue;
+        };
+
+        let Some(path) = pathfinding::route(grid, &costs, start, goal) else {
+            continue;
+        };
+
+        let indices = path
+            .hexes
+            .iter()
+            .map(|hex| grid.hex_to_index(*hex).unwrap())
+            .collect::<Vec<_>>();
+
+        for index in &indices {
+            on_road[*index] = true;
+            costs[*index] = Some(ROAD_COST);
+        }
+
+        for pair in indices.windows(2) {
+            *traffic
+                .entry((pair[0].min(pair[1]), pair[0].max(pair[1])))
+                .or_default() += 1;
+        }
+
+        roads.push(Road { from, to, path });
+    }
+
+    let segments = traffic
+        .into_iter()
+        .filter_map(|((a, b), traffic)| {
+            let origin = grid.index_to_hex(a);
+            let direction = direction(grid, origin, grid.index_to_hex(b))?;
+
+            Some(RoadSegment {
+                edge: GridEdge { origin, direction },
+                traffic,
+            })
+        })
+        .collect();
+
+    GeneratedRoads {
+        grid,
+        roads,
+        segments,
+        on_road,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        settlements::{self, SettlementParams},
+        terrain::{self, TerrainParams},
+    };
+
+    #[test]
+    fn roads_join_up_and_keep_to_land() {
+        let terrain = terrain::generate(TerrainParams::new(80, 48, 1.2, 3));
+        let settlements = settlements::generate(&terrain, SettlementParams::new(5))
+            .map(|settlement| settlement.hex)
+            .collect::<Vec<_>>();
+
+        let roads = generate(&terrain, &settlements, RoadParams::default());
+
+        assert!(!roads.roads().is_empty());
+
+        for road in roads.roads() {
+            assert_eq!(road.path.hexes.first(), Some(&settlements[road.from]));
+            assert_eq!(road.path.hexes.last(), Some(&settlements[road.to]));
+            assert!(road.path.hexes.iter().all(|hex| {
+                road_cost(terrain.get(*hex).unwrap(), terrain.biome(*hex)).is_some()
+            }));
+        }
+
+        // Shared stretches are merged, so there are fewer segments than steps along the roads
+        let steps = roads
+            .roads()
+            .iter()
+            .map(|road| road.path.hexes.len() - 1)
+            .sum::<usize>();
+        let traffic = roads
+            .segments()
+            .map(|segment| segment.traffic)
+            .sum::<usize>();
+
+        assert_eq!(traffic, steps);
+        assert!(roads.segments().count() < steps);
+        assert!(
+            roads
+                .segments()
+                .all(|segment| roads.contains(segment.edge.origin)
+                    && roads.contains(segment.edge.origin.neighbor(segment.edge.direction)))
+        );
+    }
+}
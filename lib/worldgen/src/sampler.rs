@@ -0,0 +1,166 @@
+use std::f64::consts::{PI, TAU};
+
+use hexx::{HexLayout, HexOrientation, OffsetHexMode};
+use noise::NoiseFn;
+
+use crate::grid::{HexGrid, Topology};
+
+// The combinators in `noise` only work in a single dimension, so anything built from them has to
+// be built once for 3D sampling and again for 4D
+pub struct Dimensions<Three, Four>(pub Three, pub Four);
+
+impl<Three: NoiseFn<f64, 3>, Four> NoiseFn<f64, 3> for Dimensions<Three, Four> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        self.0.get(point)
+    }
+}
+
+impl<Three, Four: NoiseFn<f64, 4>> NoiseFn<f64, 4> for Dimensions<Three, Four> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        self.1.get(point)
+    }
+}
+
+// Recovers how far down the map a point sampled on a torus came from, for noise that varies with
+// latitude
+pub fn torus_height(point: [f64; 4], height_extent: f64) -> f64 {
+    let turn = libm::atan2(point[3], point[2]).rem_euclid(TAU);
+    turn / TAU * height_extent
+}
+
+// Maps each hex of the grid onto a surface in noise space that joins up wherever the map wraps.
+// Flat maps unroll the cylinder onto a plane, and toroidal maps wrap the rows around a second
+// circle, which needs a fourth dimension.
+pub struct HexMapSampler {
+    grid: HexGrid,
+    scale_factor: f64,
+    layout: HexLayout,
+}
+
+impl HexMapSampler {
+    pub fn new(grid: HexGrid, scale_factor: f64, layout: HexLayout) -> Self {
+        Self {
+            grid,
+            scale_factor,
+            layout,
+        }
+    }
+
+    pub fn height_extent(&self) -> f64 {
+        let hex_rect = self.layout.rect_size();
+        let scale = 1. / self.scale_factor;
+
+        2. * PI
+            * scale
+            * (self.grid.height() as f64 / self.grid.width() as f64)
+            * (hex_rect.x as f64 / hex_rect.y as f64)
+    }
+
+    pub fn x_step(&self) -> f64 {
+        360.0 / self.grid.width() as f64
+    }
+
+    pub fn y_step(&self) -> f64 {
+        self.height_extent() / self.grid.height() as f64
+    }
+
+    pub fn dimensions(&self) -> usize {
+        match self.grid.topology() {
+            Topology::Torus => 4,
+            Topology::Flat | Topology::Cylinder => 3,
+        }
+    }
+
+    fn point_xy_impl(
+        grid: &HexGrid,
+        [x, y]: [i32; 2],
+        x_step: f64,
+        y_step: f64,
+        scale: f64,
+    ) -> [f64; 4] {
+        let mut current_height = y_step * y as f64;
+        let mut current_angle = x_step * x as f64;
+
+        // Offset lines sit half a hex along from their neighbours, which is down the column for
+        // flat-top hexes and across the row for pointy-top ones
+        let is_offset = |line: i32| match grid.offset_mode() {
+            OffsetHexMode::Even => line % 2 == 0,
+            OffsetHexMode::Odd => line % 2 != 0,
+        };
+
+        match grid.orientation() {
+            HexOrientation::Flat if is_offset(x) => current_height += y_step * 0.5,
+            HexOrientation::Pointy if is_offset(y) => current_angle += x_step * 0.5,
+            _ => (),
+        }
+
+        // Use libm rather than the platform intrinsics so native and wasm builds sample
+        // exactly the same points for a given seed
+        let point_x = libm::cos(current_angle.to_radians()) * scale;
+        let point_z = libm::sin(current_angle.to_radians()) * scale;
+
+        match grid.topology() {
+            Topology::Flat => [current_angle.to_radians() * scale, current_height, 0., 0.],
+            Topology::Cylinder => [point_x, current_height, point_z, 0.],
+            Topology::Torus => {
+                let height_extent = y_step * grid.height() as f64;
+                let turn = current_height / height_extent * TAU;
+                let radius = height_extent / TAU;
+
+                [
+                    point_x,
+                    point_z,
+                    libm::cos(turn) * radius,
+                    libm::sin(turn) * radius,
+                ]
+            }
+        }
+    }
+
+    pub fn sample<Noise: NoiseFn<f64, 3> + NoiseFn<f64, 4>>(
+        &self,
+        point: [f64; 4],
+        noise: &Noise,
+    ) -> f64 {
+        match self.grid.topology() {
+            Topology::Torus => noise.get(point),
+            Topology::Flat | Topology::Cylinder => noise.get([point[0], point[1], point[2]]),
+        }
+    }
+
+    pub fn sample_xy<Noise: NoiseFn<f64, 3> + NoiseFn<f64, 4>>(
+        &self,
+        x: i32,
+        y: i32,
+        noise: &Noise,
+    ) -> f64 {
+        let point = Self::point_xy_impl(
+            &self.grid,
+            [x, y],
+            self.x_step(),
+            self.y_step(),
+            1. / self.scale_factor,
+        );
+
+        self.sample(point, noise)
+    }
+
+    pub fn points(&self) -> impl ExactSizeIterator<Item = [f64; 4]> {
+        let grid = self.grid;
+        let x_step = self.x_step();
+        let y_step = self.y_step();
+        let scale = 1. / self.scale_factor;
+
+        (0..grid.len()).map(move |index| {
+            let offset = grid.offset(grid.index_to_hex(index));
+            Self::point_xy_impl(&grid, offset, x_step, y_step, scale)
+        })
+    }
+
+    pub fn generate<Noise: NoiseFn<f64, 3> + NoiseFn<f64, 4>>(
+        &self,
+        noise: Noise,
+    ) -> impl ExactSizeIterator<Item = f64> {
+        self.points().map(move |point| self.sample(point, &noise))
+    }
+}
@@ -0,0 +1,452 @@
+use std::{
+    fmt,
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use hexx::Hex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    nations::Nation,
+    settlements::{self, Settlement, SettlementParams, SettlementTier},
+    sphere::SphereParams,
+    terrain::{GeneratedTerrain, TerrainParams},
+};
+
+// Bumped whenever the layout of a world file changes, along with a migration that brings files
+// written by the previous version up to date
+pub const FORMAT_VERSION: u64 = 3;
+
+// The migration at each index takes a file from version `index + 1` to the version after it
+const MIGRATIONS: [fn(&mut Value); FORMAT_VERSION as usize - 1] =
+    [add_settlement_tiers, add_nations];
+
+// Version 2 gave settlements a tier and a population. Both depend only on the terrain around a
+// settlement, so they can be worked out again for older files. Anything that can't be read is
+// left for loading to report.
+fn add_settlement_tiers(value: &mut Value) {
+    let (Ok(params), Ok(terrain)) = (
+        serde_json::from_value::<GenerationParams>(value["params"].clone()),
+        serde_json::from_value::<GeneratedTerrain>(value["terrain"].clone()),
+    ) else {
+        return;
+    };
+
+    if !terrain.is_complete() {
+        return;
+    }
+
+    let Some(saved) = value["settlements"].as_array_mut() else {
+        return;
+    };
+
+    let Ok(hexes) = saved
+        .iter()
+        .map(|settlement| serde_json::from_value::<Hex>(settlement["hex"].clone()))
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return;
+    };
+
+    for (saved, settlement) in
+        saved
+            .iter_mut()
+            .zip(settlements::describe(&terrain, params.settlements, hexes))
+    {
+        saved["tier"] = json!(settlement.tier);
+        saved["population"] = settlement.population.into();
+    }
+}
+
+// Version 3 grouped zones into nations. Their names were made up when the world was generated, so
+// older worlds are left without any.
+fn add_nations(value: &mut Value) {
+    value["nations"] = json!([]);
+}
+
+#[derive(Debug)]
+pub enum WorldFileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    MissingVersion,
+    UnsupportedVersion(u64),
+    Invalid(&'static str),
+}
+
+impl fmt::Display for WorldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldFileError::Io(error) => write!(f, "could not access world file: {error}"),
+            WorldFileError::Json(error) => write!(f, "malformed world file: {error}"),
+            WorldFileError::MissingVersion => write!(f, "world file has no format version"),
+            WorldFileError::UnsupportedVersion(version) => write!(
+                f,
+                "world file has format version {version}, but only up to {FORMAT_VERSION} is supported"
+            ),
+            WorldFileError::Invalid(reason) => write!(f, "invalid world file: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for WorldFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WorldFileError::Io(error) => Some(error),
+            WorldFileError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WorldFileError {
+    fn from(error: std::io::Error) -> Self {
+        WorldFileError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for WorldFileError {
+    fn from(error: serde_json::Error) -> Self {
+        WorldFileError::Json(error)
+    }
+}
+
+// How the terrain of a world was made, so it can be generated again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TerrainSource {
+    Map(TerrainParams),
+    // Generated on a globe and projected onto a map of the given size
+    Globe {
+        sphere: SphereParams,
+        width: i32,
+        height: i32,
+    },
+}
+
+impl TerrainSource {
+    pub fn seed(&self) -> u64 {
+        match self {
+            TerrainSource::Map(params) => params.seed(),
+            TerrainSource::Globe { sphere, .. } => sphere.seed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationParams {
+    pub terrain: TerrainSource,
+    pub settlements: SettlementParams,
+}
+
+impl GenerationParams {
+    pub fn new(terrain: TerrainSource, settlements: SettlementParams) -> Self {
+        Self {
+            terrain,
+            settlements,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.terrain.seed()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSettlement {
+    pub hex: Hex,
+    pub name: String,
+    pub tier: SettlementTier,
+    pub population: u32,
+}
+
+impl SavedSettlement {
+    pub fn new(settlement: Settlement, name: impl Into<String>) -> Self {
+        Self {
+            hex: settlement.hex,
+            name: name.into(),
+            tier: settlement.tier,
+            population: settlement.population,
+        }
+    }
+}
+
+// A nation, with its capital and the zones it's made of given by the indices of their settlements
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedNation {
+    pub name: String,
+    pub capital: usize,
+    pub zones: Vec<usize>,
+}
+
+impl SavedNation {
+    pub fn new(nation: &Nation, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            capital: nation.capital,
+            zones: nation.zones.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldFile {
+    params: GenerationParams,
+    terrain: GeneratedTerrain,
+    settlements: Vec<SavedSettlement>,
+    // The settlement whose zone each tile falls in, in the same order as the terrain's tiles
+    zones: Vec<Option<usize>>,
+    nations: Vec<SavedNation>,
+}
+
+impl WorldFile {
+    pub fn new(params: GenerationParams, terrain: GeneratedTerrain) -> Self {
+        let zones = vec![None; terrain.grid().len()];
+
+        Self {
+            params,
+            terrain,
+            settlements: Vec::new(),
+            zones,
+            nations: Vec::new(),
+        }
+    }
+
+    pub fn with_settlements(self, settlements: impl IntoIterator<Item = SavedSettlement>) -> Self {
+        Self {
+            settlements: settlements.into_iter().collect(),
+            ..self
+        }
+    }
+
+    // Assigns each hex to the zone of the settlement at that index
+    pub fn with_zones(mut self, zones: impl IntoIterator<Item = (Hex, usize)>) -> Self {
+        let grid = self.terrain.grid();
+
+        for (hex, zone) in zones {
+            if let Some(index) = grid.hex_to_index(grid.wrap(hex)) {
+                self.zones[index] = Some(zone);
+            }
+        }
+
+        self
+    }
+
+    pub fn with_nations(self, nations: impl IntoIterator<Item = SavedNation>) -> Self {
+        Self {
+            nations: nations.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn params(&self) -> &GenerationParams {
+        &self.params
+    }
+
+    pub fn terrain(&self) -> &GeneratedTerrain {
+        &self.terrain
+    }
+
+    pub fn settlements(&self) -> &[SavedSettlement] {
+        &self.settlements
+    }
+
+    pub fn zone(&self, hex: Hex) -> Option<usize> {
+        let grid = self.terrain.grid();
+        grid.hex_to_index(grid.wrap(hex))
+            .and_then(|index| self.zones[index])
+    }
+
+    pub fn zones(&self) -> impl Iterator<Item = (Hex, usize)> {
+        let grid = self.terrain.grid();
+
+        self.zones
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, zone)| zone.map(|zone| (grid.index_to_hex(index), zone)))
+    }
+
+    pub fn nations(&self) -> &[SavedNation] {
+        &self.nations
+    }
+
+    fn validate(&self) -> Result<(), WorldFileError> {
+        if !self.terrain.is_complete() {
+            return Err(WorldFileError::Invalid(
+                "terrain does not cover the whole map",
+            ));
+        }
+
+        if self.zones.len() != self.terrain.grid().len() {
+            return Err(WorldFileError::Invalid("zones do not cover the whole map"));
+        }
+
+        if self
+            .zones
+            .iter()
+            .flatten()
+            .any(|zone| *zone >= self.settlements.len())
+        {
+            return Err(WorldFileError::Invalid(
+                "zone belongs to a missing settlement",
+            ));
+        }
+
+        if self.nations.iter().any(|nation| {
+            nation.capital >= self.settlements.len()
+                || nation
+                    .zones
+                    .iter()
+                    .any(|zone| *zone >= self.settlements.len())
+        }) {
+            return Err(WorldFileError::Invalid(
+                "nation includes a missing settlement",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn save(world: &WorldFile, writer: impl Write) -> Result<(), WorldFileError> {
+    let mut value = serde_json::to_value(world)?;
+    value["version"] = FORMAT_VERSION.into();
+
+    let mut writer = BufWriter::new(writer);
+    serde_json::to_writer(&mut writer, &value)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn load(reader: impl Read) -> Result<WorldFile, WorldFileError> {
+    let mut value: Value = serde_json::from_reader(BufReader::new(reader))?;
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(WorldFileError::MissingVersion)?;
+
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(WorldFileError::UnsupportedVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut value);
+    }
+
+    let world: WorldFile = serde_json::from_value(value)?;
+    world.validate()?;
+
+    Ok(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{settlements, terrain};
+
+    fn world() -> WorldFile {
+        let terrain_params = TerrainParams::new(40, 24, 1.2, 7);
+        let settlement_params = SettlementParams::new(3);
+
+        let terrain = terrain::generate(terrain_params.clone());
+        let settlements = settlements::generate(&terrain, settlement_params)
+            .enumerate()
+            .map(|(index, settlement)| {
+                SavedSettlement::new(settlement, format!("Settlement {index}"))
+            })
+            .collect::<Vec<_>>();
+
+        let nation = Nation {
+            capital: 0,
+            zones: (0..settlements.len()).collect(),
+        };
+
+        let zones = terrain
+            .tiles()
+            .enumerate()
+            .map(|(index, (hex, _))| (hex, index % settlements.len()))
+            .collect::<Vec<_>>();
+
+        WorldFile::new(
+            GenerationParams::new(TerrainSource::Map(terrain_params), settlement_params),
+            terrain,
+        )
+        .with_settlements(settlements)
+        .with_zones(zones)
+        .with_nations([SavedNation::new(&nation, "Nation")])
+    }
+
+    #[test]
+    fn world_files_round_trip_exactly() {
+        let world = world();
+
+        let mut saved = Vec::new();
+        save(&world, &mut saved).unwrap();
+
+        let loaded = load(saved.as_slice()).unwrap();
+
+        assert!(
+            world
+                .terrain()
+                .elevations()
+                .zip(loaded.terrain().elevations())
+                .all(|(a, b)| a.0 == b.0 && a.1.to_bits() == b.1.to_bits())
+        );
+        assert!(world.terrain().tiles().eq(loaded.terrain().tiles()));
+        assert!(world.terrain().climates().eq(loaded.terrain().climates()));
+        assert_eq!(world.settlements(), loaded.settlements());
+        assert!(world.zones().eq(loaded.zones()));
+        assert_eq!(world.nations(), loaded.nations());
+
+        let mut resaved = Vec::new();
+        save(&loaded, &mut resaved).unwrap();
+
+        assert_eq!(saved, resaved);
+    }
+
+    #[test]
+    fn older_settlements_are_given_tiers() {
+        let world = world();
+
+        // Version 1 files only had a position and a name for each settlement
+        let mut value = serde_json::to_value(&world).unwrap();
+        value["version"] = 1.into();
+
+        for settlement in value["settlements"].as_array_mut().unwrap() {
+            let settlement = settlement.as_object_mut().unwrap();
+            settlement.remove("tier");
+            settlement.remove("population");
+        }
+
+        let loaded = load(value.to_string().as_bytes()).unwrap();
+
+        assert_eq!(world.settlements(), loaded.settlements());
+    }
+
+    #[test]
+    fn older_worlds_load_without_nations() {
+        let mut value = serde_json::to_value(world()).unwrap();
+        value["version"] = 2.into();
+        value.as_object_mut().unwrap().remove("nations");
+
+        assert!(
+            load(value.to_string().as_bytes())
+                .unwrap()
+                .nations()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut value = serde_json::to_value(world()).unwrap();
+        value["version"] = (FORMAT_VERSION + 1).into();
+
+        assert!(matches!(
+            load(value.to_string().as_bytes()),
+            Err(WorldFileError::UnsupportedVersion(_))
+        ));
+    }
+}
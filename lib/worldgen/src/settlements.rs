@@ -1,12 +1,21 @@
 use std::collections::HashMap;
 
 use fast_poisson::Poisson2D;
-use hexx::{Hex, HexOrientation, OffsetHexMode};
+use hexx::Hex;
 use noise::{Fbm, MultiFractal, Perlin, ScaleBias, Seedable};
 
-use crate::{cylinder::CylindricalHexMapSampler, terrain::GeneratedTerrain};
+use crate::{
+    rivers::{self, RiverParams},
+    sampler::{Dimensions, HexMapSampler},
+    terrain::{GeneratedTerrain, TerrainType},
+};
+
+// Settlements of at least this many people are towns or cities
+const TOWN_POPULATION: u32 = 7_500;
+const CITY_POPULATION: u32 = 25_000;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SettlementParams {
     seed: u32,
 }
@@ -17,33 +26,140 @@ impl SettlementParams {
     }
 }
 
-pub fn generate(
-    terrain: &GeneratedTerrain,
-    SettlementParams { seed }: SettlementParams,
-) -> impl Iterator<Item = Hex> {
-    let noise =
-        ScaleBias::new(Fbm::<Perlin>::default().set_seed(seed).set_frequency(2.)).set_bias(0.5);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SettlementTier {
+    Village,
+    Town,
+    City,
+}
+
+impl SettlementTier {
+    fn from_population(population: u32) -> Self {
+        if population >= CITY_POPULATION {
+            SettlementTier::City
+        } else if population >= TOWN_POPULATION {
+            SettlementTier::Town
+        } else {
+            SettlementTier::Village
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settlement {
+    pub hex: Hex,
+    pub tier: SettlementTier,
+    pub population: u32,
+}
+
+type SpacingNoise = Dimensions<ScaleBias<f64, Fbm<Perlin>, 3>, ScaleBias<f64, Fbm<Perlin>, 4>>;
+
+// How far apart settlements are spread around each hex, from 0 where they crowd together to 1
+// where they're furthest apart
+struct Spacing {
+    noise: SpacingNoise,
+    sampler: HexMapSampler,
+}
+
+impl Spacing {
+    fn new(terrain: &GeneratedTerrain, seed: u32) -> Self {
+        let source = Fbm::<Perlin>::default().set_seed(seed).set_frequency(2.);
+
+        Self {
+            noise: Dimensions(
+                ScaleBias::<_, _, 3>::new(source.clone()).set_bias(0.5),
+                ScaleBias::<_, _, 4>::new(source).set_bias(0.5),
+            ),
+            sampler: HexMapSampler::new(terrain.grid(), 4., terrain.layout().clone()),
+        }
+    }
+
+    fn at(&self, [x, y]: [i32; 2]) -> f64 {
+        self.sampler.sample_xy(x, y, &self.noise).clamp(0., 1.)
+    }
+}
+
+// Sizes up settlements from their surroundings. Those with plenty of farmland, room to spread out,
+// a harbour or a river to trade along grow the largest.
+struct Growth<'a> {
+    terrain: &'a GeneratedTerrain,
+    spacing: Spacing,
+    // The largest flow of any river along the edges of each hex
+    rivers: HashMap<Hex, f64>,
+}
+
+impl<'a> Growth<'a> {
+    fn new(terrain: &'a GeneratedTerrain, seed: u32) -> Self {
+        let mut rivers = HashMap::<Hex, f64>::new();
 
-    let width = terrain.width();
-    let height = terrain.height();
+        for segment in rivers::generate(terrain, RiverParams::default()).segments() {
+            let origin = segment.edge.origin;
 
-    let hex_fn = move |x: i32, y: i32| {
-        let x = x.clamp(0, width) + 1;
-        let mut y = y.clamp(0, height) + 1;
+            for hex in [origin, origin.neighbor(segment.edge.direction)] {
+                let flow = rivers.entry(terrain.wrap(hex)).or_default();
+                *flow = flow.max(segment.flow);
+            }
+        }
+
+        Self {
+            terrain,
+            spacing: Spacing::new(terrain, seed),
+            rivers,
+        }
+    }
+
+    fn settle(&self, hex: Hex) -> Settlement {
+        let terrain = self.terrain;
+        let hex = terrain.wrap(hex);
 
-        if x % 2 == 1 {
-            y += 1
+        let (habitable, total) = hex
+            .range(2)
+            .filter_map(|hex| terrain.get(hex))
+            .fold((0, 0), |(habitable, total), tile| {
+                (habitable + tile.is_habitable() as u32, total + 1)
+            });
+
+        let habitability = habitable as f64 / total.max(1) as f64;
+        let spacing = self.spacing.at(terrain.grid().offset(hex));
+
+        let coastal = terrain.neighbors(hex).any(|neighbour| {
+            matches!(
+                terrain.get(neighbour),
+                Some(
+                    TerrainType::Coast
+                        | TerrainType::ShallowOcean
+                        | TerrainType::DeepOcean
+                        | TerrainType::Lake
+                )
+            )
+        });
+
+        // Bigger rivers carry more trade, up to a point
+        let river = self.rivers.get(&hex).map_or(0., |flow| {
+            (0.2 + 0.3 * libm::log10(flow / RiverParams::default().flow_threshold())).min(0.8)
+        });
+
+        let importance = habitability + spacing + if coastal { 0.4 } else { 0. } + river;
+        let population = (50. * libm::pow(10., 1.5 * importance) / 10.).round() as u32 * 10;
+
+        Settlement {
+            hex,
+            tier: SettlementTier::from_population(population),
+            population,
         }
+    }
+}
 
-        Hex::from_offset_coordinates([x, y], OffsetHexMode::Even, HexOrientation::Flat)
-    };
+fn positions(terrain: &GeneratedTerrain, seed: u32) -> impl Iterator<Item = Hex> {
+    let grid = terrain.grid();
+    let width = grid.width();
+    let height = grid.height();
 
-    let habitability = terrain
-        .tiles()
-        .map(|(hex, tile)| (hex, tile.is_habitable()))
-        .collect::<HashMap<_, _>>();
+    let hex_fn =
+        move |x: i32, y: i32| grid.cell_to_hex([x.clamp(0, width) + 1, y.clamp(0, height) + 1]);
 
-    let sampler = CylindricalHexMapSampler::new(width, height, 4., terrain.layout().clone());
+    let spacing = Spacing::new(terrain, seed);
 
     Poisson2D::new()
         .with_dimensions([width as f64, height as f64], move |[x, y]: [f64; 2]| {
@@ -51,17 +167,38 @@ pub fn generate(
             let max_radius = 10.;
 
             let hex = hex_fn(x as i32, y as i32);
-            let [x, y] = hex.to_offset_coordinates(OffsetHexMode::Even, HexOrientation::Flat);
 
-            if habitability.get(&hex).is_some_and(|pair| *pair) {
-                let value = sampler.sample_xy(x, y, &noise).clamp(0., 1.);
+            if terrain.get(hex).is_some_and(|tile| tile.is_habitable()) {
+                let value = spacing.at(grid.offset(hex));
                 Some((value * (max_radius - min_radius)) + min_radius)
             } else {
                 None
             }
         })
-        .with_wrapping([true, false])
+        .with_wrapping([grid.topology().wraps_x(), grid.topology().wraps_y()])
         .with_seed(seed as u64 + 1)
         .into_iter()
         .map(move |[x, y]| hex_fn(x as i32, y as i32))
 }
+
+pub fn generate(
+    terrain: &GeneratedTerrain,
+    SettlementParams { seed }: SettlementParams,
+) -> impl Iterator<Item = Settlement> {
+    let growth = Growth::new(terrain, seed);
+
+    positions(terrain, seed).map(move |hex| growth.settle(hex))
+}
+
+// Sizes up settlements that have already been placed, giving the same results as `generate` for
+// the settlements it placed
+#[cfg(feature = "serde")]
+pub(crate) fn describe(
+    terrain: &GeneratedTerrain,
+    SettlementParams { seed }: SettlementParams,
+    hexes: impl IntoIterator<Item = Hex>,
+) -> impl Iterator<Item = Settlement> {
+    let growth = Growth::new(terrain, seed);
+
+    hexes.into_iter().map(move |hex| growth.settle(hex))
+}
@@ -0,0 +1,418 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_2, PI, TAU},
+};
+
+use hexx::HexLayout;
+use noise::NoiseFn;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    grid::{CellGraph, HexGrid},
+    terrain::{
+        Climate, ErosionParams, GeneratedTerrain, TerrainClassifier, TerrainType, climate, erosion,
+        lakes,
+        noise::{NoiseParameters, get_noise_fn},
+        polar,
+    },
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SphereParams {
+    subdivisions: u32,
+    scale_factor: f64,
+    seed: u64,
+    classifier: TerrainClassifier,
+    polar_band: f64,
+    ocean_share: f64,
+    erosion: Option<ErosionParams>,
+}
+
+impl SphereParams {
+    pub fn new(subdivisions: u32, scale_factor: f64, seed: u64) -> Self {
+        Self {
+            subdivisions,
+            scale_factor,
+            seed,
+            classifier: TerrainClassifier::default(),
+            polar_band: 5.,
+            ocean_share: 0.6,
+            erosion: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn with_classifier(self, classifier: TerrainClassifier) -> Self {
+        Self { classifier, ..self }
+    }
+
+    // How many degrees of latitude around each pole are frozen over
+    pub fn with_polar_band(self, degrees: f64) -> Self {
+        Self {
+            polar_band: degrees,
+            ..self
+        }
+    }
+
+    // How much of the globe ends up under water
+    pub fn with_ocean_share(self, ocean_share: f64) -> Self {
+        Self {
+            ocean_share,
+            ..self
+        }
+    }
+
+    pub fn with_erosion(self, erosion: ErosionParams) -> Self {
+        Self {
+            erosion: Some(erosion),
+            ..self
+        }
+    }
+}
+
+// The cells of a Goldberg polyhedron, found as the vertices of a subdivided icosahedron. The 12
+// corners of the icosahedron become pentagons and every other cell is a hexagon.
+#[derive(Debug, Clone)]
+pub struct GeodesicGrid {
+    positions: Vec<[f64; 3]>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+fn normalise(point: [f64; 3]) -> [f64; 3] {
+    let length = point.iter().map(|axis| axis * axis).sum::<f64>().sqrt();
+    point.map(|axis| axis / length)
+}
+
+fn distance_squared(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+fn icosahedron() -> ([[f64; 3]; 12], Vec<[usize; 3]>) {
+    let phi = (1. + 5f64.sqrt()) / 2.;
+
+    let corners = [
+        [-1., phi, 0.],
+        [1., phi, 0.],
+        [-1., -phi, 0.],
+        [1., -phi, 0.],
+        [0., -1., phi],
+        [0., 1., phi],
+        [0., -1., -phi],
+        [0., 1., -phi],
+        [phi, 0., -1.],
+        [phi, 0., 1.],
+        [-phi, 0., -1.],
+        [-phi, 0., 1.],
+    ];
+
+    // Every edge has a length of 2, so the faces are the triples of corners that are all that
+    // far from each other
+    let is_edge = |a: usize, b: usize| (distance_squared(corners[a], corners[b]) - 4.).abs() < 1e-9;
+
+    let mut faces = Vec::new();
+
+    for a in 0..12 {
+        for b in (a + 1)..12 {
+            for c in (b + 1)..12 {
+                if is_edge(a, b) && is_edge(b, c) && is_edge(a, c) {
+                    faces.push([a, b, c]);
+                }
+            }
+        }
+    }
+
+    (corners, faces)
+}
+
+impl GeodesicGrid {
+    pub fn new(subdivisions: u32) -> Self {
+        let subdivisions = subdivisions.max(1);
+        let (corners, faces) = icosahedron();
+
+        // Points along the edges of the icosahedron are shared between faces, so each point is
+        // identified by how much of each corner it is made from
+        let mut indices = HashMap::<Vec<(usize, u32)>, usize>::new();
+        let mut positions = Vec::new();
+        let mut edges = Vec::new();
+
+        for face in faces {
+            let mut local = HashMap::new();
+
+            for i in 0..=subdivisions {
+                for j in 0..=(subdivisions - i) {
+                    let mut weights = [(face[0], subdivisions - i - j), (face[1], i), (face[2], j)]
+                        .into_iter()
+                        .filter(|(_, weight)| *weight > 0)
+                        .collect::<Vec<_>>();
+
+                    weights.sort();
+
+                    let index = *indices.entry(weights.clone()).or_insert_with(|| {
+                        let point = weights.iter().fold([0.; 3], |point, (corner, weight)| {
+                            std::array::from_fn(|axis| {
+                                point[axis] + corners[*corner][axis] * *weight as f64
+                            })
+                        });
+
+                        positions.push(normalise(point));
+                        positions.len() - 1
+                    });
+
+                    local.insert((i, j), index);
+                }
+            }
+
+            for ((i, j), index) in &local {
+                for (di, dj) in [(1, 0), (0, 1), (-1, 1)] {
+                    let neighbour = (i.wrapping_add_signed(di), j.wrapping_add_signed(dj));
+
+                    if let Some(neighbour) = local.get(&neighbour) {
+                        edges.push((*index, *neighbour));
+                    }
+                }
+            }
+        }
+
+        let mut neighbours = vec![Vec::new(); positions.len()];
+
+        for (a, b) in edges {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+
+        for neighbours in &mut neighbours {
+            neighbours.sort();
+            neighbours.dedup();
+        }
+
+        Self {
+            positions,
+            neighbours,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // Where the centre of the cell sits on the unit sphere
+    pub fn position(&self, index: usize) -> [f64; 3] {
+        self.positions[index]
+    }
+
+    // In radians, from -π/2 at the south pole to π/2 at the north
+    pub fn latitude(&self, index: usize) -> f64 {
+        libm::asin(self.positions[index][1].clamp(-1., 1.))
+    }
+
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        self.neighbours[index].iter().copied()
+    }
+
+    pub fn is_pentagon(&self, index: usize) -> bool {
+        self.neighbours[index].len() == 5
+    }
+
+    // Walks across the grid from `start` towards the cell closest to `position`, which is much
+    // quicker than checking every cell when the positions being looked up are close together
+    pub fn nearest(&self, position: [f64; 3], start: usize) -> usize {
+        let distance = |index: usize| distance_squared(self.positions[index], position);
+
+        let mut current = start;
+
+        while let Some(next) = self
+            .neighbours(current)
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+            .filter(|next| distance(*next) < distance(current))
+        {
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl CellGraph for GeodesicGrid {
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        GeodesicGrid::neighbours(self, index)
+    }
+
+    fn is_edge(&self, _: usize) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedSphere {
+    grid: GeodesicGrid,
+    tiles: Vec<TerrainType>,
+    elevations: Vec<f64>,
+    climate: Vec<Climate>,
+}
+
+impl GeneratedSphere {
+    pub fn grid(&self) -> &GeodesicGrid {
+        &self.grid
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, TerrainType)> {
+        self.tiles.iter().copied().enumerate()
+    }
+
+    pub fn elevations(&self) -> impl Iterator<Item = (usize, f64)> {
+        self.elevations.iter().copied().enumerate()
+    }
+
+    pub fn climates(&self) -> impl Iterator<Item = (usize, Climate)> {
+        self.climate.iter().copied().enumerate()
+    }
+
+    // Unwraps the globe onto a flat-top hex map, with longitude running across the columns and
+    // latitude down the rows, so everything that works on a map can run on the globe too
+    pub fn project(&self, width: i32, height: i32) -> GeneratedTerrain {
+        let grid = HexGrid::new(width, height);
+        let mut current = 0;
+
+        let cells = (0..grid.len())
+            .map(|index| {
+                let [x, y] = grid.index_to_cell(index);
+
+                let longitude = (x as f64 - 0.5) / width as f64 * TAU;
+                let latitude = FRAC_PI_2 - (y as f64 - 0.5) / height as f64 * PI;
+
+                let position = [
+                    libm::cos(latitude) * libm::cos(longitude),
+                    libm::sin(latitude),
+                    libm::cos(latitude) * libm::sin(longitude),
+                ];
+
+                current = self.grid.nearest(position, current);
+                current
+            })
+            .collect::<Vec<_>>();
+
+        GeneratedTerrain::new(
+            grid,
+            HexLayout::flat().with_hex_size(2.),
+            cells.iter().map(|cell| self.tiles[*cell]).collect(),
+            cells.iter().map(|cell| self.elevations[*cell]).collect(),
+            cells.iter().map(|cell| self.climate[*cell]).collect(),
+        )
+    }
+}
+
+pub fn generate(
+    SphereParams {
+        subdivisions,
+        scale_factor,
+        seed,
+        classifier,
+        polar_band,
+        ocean_share,
+        erosion,
+    }: SphereParams,
+) -> GeneratedSphere {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let grid = GeodesicGrid::new(subdivisions);
+    let radius = 1. / scale_factor;
+
+    // Shift the sphere up so it sits between 0 and its diameter, which is the range the
+    // latitude-aware noise expects
+    let points = (0..grid.len())
+        .map(|index| {
+            let [x, y, z] = grid.position(index);
+            [x * radius, (y + 1.) * radius, z * radius]
+        })
+        .collect::<Vec<_>>();
+
+    let sample = |noise: &dyn NoiseFn<f64, 3>| {
+        points
+            .iter()
+            .map(|point| noise.get(*point))
+            .collect::<Vec<_>>()
+    };
+
+    // There are no map edges to push the continents away from
+    let noise = get_noise_fn(NoiseParameters::new(rng.random(), radius * 2., 0., 0.6));
+
+    let moisture = climate::get_moisture_fn(rng.random());
+    let temperature = climate::get_temperature_fn(rng.random(), radius * 2.);
+    let ice = polar::get_ice_fn(rng.random());
+
+    let mut elevations = sample(&noise);
+
+    if let Some(erosion) = erosion {
+        erosion::erode(&grid, &mut elevations, erosion, rng.random());
+    }
+
+    // Maps get their oceans from the continents being pushed away from the top and bottom edges,
+    // so a globe has to set its sea level to get any
+    let mut sorted = elevations.clone();
+    sorted.sort_by(f64::total_cmp);
+
+    let shoreline = sorted[((sorted.len() as f64 * ocean_share) as usize).min(sorted.len() - 1)];
+    let shift = classifier.sea_level() - shoreline;
+
+    for elevation in &mut elevations {
+        *elevation += shift;
+    }
+
+    let mut tiles = elevations
+        .iter()
+        .map(|value| classifier.classify(*value))
+        .collect::<Vec<_>>();
+
+    lakes::mark_lakes(&grid, &mut tiles);
+
+    polar::apply_ice_caps(
+        &mut tiles,
+        polar_band,
+        (0..grid.len()).map(|index| 90. - grid.latitude(index).abs().to_degrees()),
+        sample(&ice).into_iter(),
+    );
+
+    let climate = tiles
+        .iter()
+        .zip(sample(&temperature).into_iter().zip(sample(&moisture)))
+        .map(|(tile, (temperature, moisture))| Climate::new(*tile, temperature, moisture))
+        .collect();
+
+    GeneratedSphere {
+        grid,
+        tiles,
+        elevations,
+        climate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geodesic_grid_has_twelve_pentagons() {
+        for subdivisions in [1, 2, 5, 16] {
+            let grid = GeodesicGrid::new(subdivisions);
+
+            assert_eq!(grid.len(), (10 * subdivisions * subdivisions + 2) as usize);
+            assert_eq!(
+                (0..grid.len())
+                    .filter(|index| grid.is_pentagon(*index))
+                    .count(),
+                12
+            );
+            assert!((0..grid.len()).all(|index| grid.neighbours(index).count() >= 5));
+            assert!((0..grid.len()).all(|index| grid.neighbours(index).count() <= 6));
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+
+use super::TerrainType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainBand {
+    pub below: f64,
+    pub terrain: TerrainType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerrainClassifier {
+    pub ocean_percentage: f64,
+    pub mountain_percentage: f64,
+
+    // Each section is split into bands by how far through the section a value is, from 0 to 1.
+    // Values past the final band's threshold fall into the final band.
+    pub ocean: Vec<TerrainBand>,
+    pub land: Vec<TerrainBand>,
+    pub mountains: Vec<TerrainBand>,
+}
+
+impl TerrainBand {
+    pub fn new(below: f64, terrain: TerrainType) -> Self {
+        Self { below, terrain }
+    }
+}
+
+// The original classification, with the ocean and mountain bands measured from the bottom and to
+// the top of the range
+impl Default for TerrainClassifier {
+    fn default() -> Self {
+        Self {
+            ocean_percentage: 0.44,
+            mountain_percentage: 0.32,
+            ocean: vec![
+                TerrainBand::new(0.8314, TerrainType::DeepOcean),
+                TerrainBand::new(0.9518, TerrainType::ShallowOcean),
+                TerrainBand::new(1., TerrainType::Coast),
+            ],
+            land: vec![
+                TerrainBand::new(0.085, TerrainType::Beach),
+                TerrainBand::new(0.6, TerrainType::Plains),
+                TerrainBand::new(1., TerrainType::Hills),
+            ],
+            mountains: vec![
+                TerrainBand::new(0.1211, TerrainType::LowMountains),
+                TerrainBand::new(0.2906, TerrainType::HighMountains),
+                TerrainBand::new(1., TerrainType::Peaks),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassifierError {
+    PercentageOutOfRange,
+    PercentagesOverlap,
+    EmptySection(&'static str),
+}
+
+impl fmt::Display for ClassifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassifierError::PercentageOutOfRange => {
+                write!(f, "ocean and mountain percentages must be between 0 and 1")
+            }
+            ClassifierError::PercentagesOverlap => {
+                write!(f, "ocean and mountain percentages must add up to at most 1")
+            }
+            ClassifierError::EmptySection(section) => {
+                write!(f, "the {section} section must contain at least one band")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClassifierError {}
+
+fn get_band(bands: &[TerrainBand], percentage: f64) -> TerrainType {
+    bands
+        .iter()
+        .find(|band| percentage < band.below)
+        .or(bands.last())
+        .expect("terrain classifier sections must contain at least one band")
+        .terrain
+}
+
+// How far through the section from `bottom` to `top` a value is
+fn section(value: f64, bottom: f64, top: f64) -> f64 {
+    (value - bottom) / (top - bottom)
+}
+
+impl TerrainClassifier {
+    // Presets are written by hand, so check one makes sense before classifying anything with it
+    pub fn validate(&self) -> Result<(), ClassifierError> {
+        let percentages = [self.ocean_percentage, self.mountain_percentage];
+
+        if !percentages
+            .iter()
+            .all(|percentage| (0. ..=1.).contains(percentage))
+        {
+            return Err(ClassifierError::PercentageOutOfRange);
+        }
+
+        if self.ocean_percentage + self.mountain_percentage > 1. {
+            return Err(ClassifierError::PercentagesOverlap);
+        }
+
+        for (name, bands) in [
+            ("ocean", &self.ocean),
+            ("land", &self.land),
+            ("mountains", &self.mountains),
+        ] {
+            if bands.is_empty() {
+                return Err(ClassifierError::EmptySection(name));
+            }
+        }
+
+        Ok(())
+    }
+
+    // The raw value at or below which everything is classified as water
+    pub(crate) fn sea_level(&self) -> f64 {
+        2. * self.ocean_percentage - 1.
+    }
+
+    fn mountain_level(&self) -> f64 {
+        1. - 2. * self.mountain_percentage
+    }
+
+    // Raw values run from -1 to 1, split into ocean, land and mountain sections by the percentages
+    pub fn classify(&self, value: f64) -> TerrainType {
+        let sea_level = self.sea_level();
+        let mountain_level = self.mountain_level();
+
+        if value <= sea_level {
+            get_band(&self.ocean, section(value, -1., sea_level))
+        } else if value <= mountain_level {
+            get_band(&self.land, section(value, sea_level, mountain_level))
+        } else {
+            get_band(&self.mountains, section(value, mountain_level, 1.))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // How terrain was classified before presets existed
+    fn baseline(value: f64) -> TerrainType {
+        let to_range = |value| (value / 1.67) - 0.4;
+
+        let value = to_range(value + 1.);
+
+        let land_threshold = to_range(2. * 0.44);
+        let mountain_threshold = to_range(2. * (1. - 0.32));
+
+        if value <= land_threshold {
+            let percentage = value / land_threshold;
+
+            if percentage < 0.3 {
+                TerrainType::DeepOcean
+            } else if percentage < 0.8 {
+                TerrainType::ShallowOcean
+            } else {
+                TerrainType::Coast
+            }
+        } else if value <= mountain_threshold {
+            let percentage = (value - land_threshold) / (mountain_threshold - land_threshold);
+
+            if percentage < 0.085 {
+                TerrainType::Beach
+            } else if percentage < 0.6 {
+                TerrainType::Plains
+            } else {
+                TerrainType::Hills
+            }
+        } else {
+            let percentage = (value - mountain_threshold) / (0.6 - mountain_threshold);
+
+            if percentage < 0.25 {
+                TerrainType::LowMountains
+            } else if percentage < 0.6 {
+                TerrainType::HighMountains
+            } else {
+                TerrainType::Peaks
+            }
+        }
+    }
+
+    fn values() -> impl Iterator<Item = f64> {
+        (0..200).map(|step| -0.995 + step as f64 * 0.01)
+    }
+
+    fn count(classifier: &TerrainClassifier, terrain: TerrainType) -> usize {
+        values()
+            .filter(|value| classifier.classify(*value) == terrain)
+            .count()
+    }
+
+    #[test]
+    fn the_default_matches_the_original_classification() {
+        let classifier = TerrainClassifier::default();
+
+        assert_eq!(classifier.validate(), Ok(()));
+
+        for value in values() {
+            assert_eq!(classifier.classify(value), baseline(value), "{value}");
+        }
+    }
+
+    #[test]
+    fn extreme_presets_keep_every_band() {
+        let waterworld = TerrainClassifier {
+            ocean_percentage: 0.9,
+            mountain_percentage: 0.05,
+            ..TerrainClassifier::default()
+        };
+        let mountain_world = TerrainClassifier {
+            ocean_percentage: 0.2,
+            mountain_percentage: 0.6,
+            ..TerrainClassifier::default()
+        };
+
+        for classifier in [&waterworld, &mountain_world] {
+            assert_eq!(classifier.validate(), Ok(()));
+
+            for terrain in [
+                TerrainType::DeepOcean,
+                TerrainType::ShallowOcean,
+                TerrainType::Coast,
+                TerrainType::Plains,
+                TerrainType::LowMountains,
+                TerrainType::HighMountains,
+                TerrainType::Peaks,
+            ] {
+                assert!(count(classifier, terrain) > 0, "{terrain:?}");
+            }
+        }
+
+        // Most of a waterworld is open ocean, not coast
+        assert!(count(&waterworld, TerrainType::DeepOcean) > 140);
+        assert!(count(&waterworld, TerrainType::Coast) < 10);
+        assert!(count(&mountain_world, TerrainType::Peaks) > 40);
+    }
+
+    #[test]
+    fn invalid_presets_are_rejected() {
+        let default = TerrainClassifier::default();
+
+        let too_much = TerrainClassifier {
+            ocean_percentage: 0.7,
+            mountain_percentage: 0.4,
+            ..default.clone()
+        };
+        let negative = TerrainClassifier {
+            mountain_percentage: -0.1,
+            ..default.clone()
+        };
+        let empty = TerrainClassifier {
+            land: Vec::new(),
+            ..default
+        };
+
+        assert_eq!(
+            too_much.validate(),
+            Err(ClassifierError::PercentagesOverlap)
+        );
+        assert_eq!(
+            negative.validate(),
+            Err(ClassifierError::PercentageOutOfRange)
+        );
+        assert_eq!(empty.validate(), Err(ClassifierError::EmptySection("land")));
+    }
+}
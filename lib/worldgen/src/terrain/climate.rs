@@ -0,0 +1,139 @@
+use std::f64::consts::TAU;
+
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin, ScaleBias, Seedable};
+
+use crate::sampler::{self, Dimensions};
+
+use super::TerrainType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Biome {
+    Desert,
+    Grassland,
+    Forest,
+    Jungle,
+    Taiga,
+    Tundra,
+    Ice,
+    Swamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Climate {
+    pub moisture: f64,
+    pub temperature: f64,
+    pub biome: Option<Biome>,
+}
+
+pub fn get_moisture_fn(seed: u32) -> impl NoiseFn<f64, 3> + NoiseFn<f64, 4> {
+    let source = Fbm::<Perlin>::default()
+        .set_seed(seed)
+        .set_frequency(1.5)
+        .set_octaves(6);
+
+    Dimensions(moisture::<3>(source.clone()), moisture::<4>(source))
+}
+
+fn moisture<const DIM: usize>(source: Fbm<Perlin>) -> impl NoiseFn<f64, DIM>
+where
+    Fbm<Perlin>: NoiseFn<f64, DIM>,
+{
+    ScaleBias::new(source).set_scale(0.5).set_bias(0.5)
+}
+
+pub fn get_temperature_fn(seed: u32, height_extent: f64) -> impl NoiseFn<f64, 3> + NoiseFn<f64, 4> {
+    LatitudeWrapper {
+        overall_extent: height_extent,
+        variation: 0.15,
+        source: Fbm::<Perlin>::default()
+            .set_seed(seed)
+            .set_frequency(2.)
+            .set_octaves(4),
+    }
+}
+
+struct LatitudeWrapper<Source> {
+    overall_extent: f64,
+    variation: f64,
+
+    source: Source,
+}
+
+impl<T> LatitudeWrapper<T> {
+    fn apply(&self, inner: f64, y: f64) -> f64 {
+        // 0 at either pole, 1 at the equator
+        let latitude = (y / self.overall_extent).clamp(0., 1.);
+        let warmth = 1. - (2. * latitude - 1.).abs();
+
+        warmth + inner * self.variation
+    }
+}
+
+impl<T: NoiseFn<f64, 3>> NoiseFn<f64, 3> for LatitudeWrapper<T> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        self.apply(self.source.get(point), point[1])
+    }
+}
+
+// A torus has no poles. Its warmth rises and falls once around the rows instead, coolest halfway
+// down the map and warmest across the seam, without ever getting as cold as a pole.
+impl<T: NoiseFn<f64, 4>> NoiseFn<f64, 4> for LatitudeWrapper<T> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        let turn = sampler::torus_height(point, self.overall_extent) / self.overall_extent * TAU;
+        let warmth = 0.6 + 0.25 * libm::cos(turn);
+
+        warmth + self.source.get(point) * self.variation
+    }
+}
+
+fn elevation_chill(terrain: TerrainType) -> f64 {
+    match terrain {
+        TerrainType::LowMountains => 0.08,
+        TerrainType::HighMountains => 0.15,
+        TerrainType::Peaks => 0.25,
+        _ => 0.,
+    }
+}
+
+fn get_biome(terrain: TerrainType, temperature: f64, moisture: f64) -> Option<Biome> {
+    if !terrain.is_land() {
+        return None;
+    }
+
+    Some(if terrain == TerrainType::Glacier || temperature < 0.15 {
+        Biome::Ice
+    } else if temperature < 0.3 {
+        Biome::Tundra
+    } else if temperature < 0.45 {
+        if moisture > 0.4 {
+            Biome::Taiga
+        } else {
+            Biome::Tundra
+        }
+    } else if moisture < 0.35 {
+        Biome::Desert
+    } else if moisture < 0.5 {
+        Biome::Grassland
+    } else if moisture > 0.68 && matches!(terrain, TerrainType::Beach | TerrainType::Plains) {
+        Biome::Swamp
+    } else if temperature > 0.7 {
+        Biome::Jungle
+    } else {
+        Biome::Forest
+    })
+}
+
+impl Climate {
+    pub fn new(terrain: TerrainType, temperature: f64, moisture: f64) -> Self {
+        let temperature = (temperature - elevation_chill(terrain)).clamp(0., 1.);
+        let moisture = moisture.clamp(0., 1.);
+
+        Self {
+            moisture,
+            temperature,
+            biome: get_biome(terrain, temperature, moisture),
+        }
+    }
+}
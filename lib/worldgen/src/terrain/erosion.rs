@@ -0,0 +1,184 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::grid::CellGraph;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErosionParams {
+    hydraulic_iterations: usize,
+    thermal_iterations: usize,
+}
+
+impl ErosionParams {
+    pub fn new(hydraulic_iterations: usize, thermal_iterations: usize) -> Self {
+        Self {
+            hydraulic_iterations,
+            thermal_iterations,
+        }
+    }
+}
+
+impl Default for ErosionParams {
+    fn default() -> Self {
+        Self::new(20_000, 10)
+    }
+}
+
+const DROPLET_LIFETIME: usize = 64;
+const SEDIMENT_CAPACITY: f64 = 1.;
+const EROSION_RATE: f64 = 0.1;
+const DEPOSITION_RATE: f64 = 0.3;
+const EVAPORATION_RATE: f64 = 0.02;
+
+const TALUS_THRESHOLD: f64 = 0.05;
+const THERMAL_RATE: f64 = 0.25;
+
+fn lowest_neighbour(neighbours: &[usize], elevations: &[f64]) -> Option<usize> {
+    neighbours
+        .iter()
+        .copied()
+        .min_by(|a, b| elevations[*a].total_cmp(&elevations[*b]))
+}
+
+// Each droplet runs downhill picking up material on steep slopes and dropping it as it slows,
+// so everything taken from one tile ends up on another
+fn hydraulic(
+    neighbours: &[Vec<usize>],
+    elevations: &mut [f64],
+    iterations: usize,
+    rng: &mut impl Rng,
+) {
+    for _ in 0..iterations {
+        let mut index = rng.random_range(0..elevations.len());
+        let mut sediment = 0.;
+        let mut water = 1.;
+
+        for _ in 0..DROPLET_LIFETIME {
+            let Some(next) = lowest_neighbour(&neighbours[index], elevations) else {
+                break;
+            };
+
+            let slope = elevations[index] - elevations[next];
+
+            if slope <= 0. {
+                // Stuck in a pit, so fill it with whatever is being carried
+                break;
+            }
+
+            let capacity = slope * water * SEDIMENT_CAPACITY;
+
+            if sediment > capacity {
+                let deposit = (sediment - capacity) * DEPOSITION_RATE;
+                elevations[index] += deposit;
+                sediment -= deposit;
+            } else {
+                // Never dig deeper than the tile being flowed into
+                let eroded = ((capacity - sediment) * EROSION_RATE).min(slope * 0.5);
+                elevations[index] -= eroded;
+                sediment += eroded;
+            }
+
+            water *= 1. - EVAPORATION_RATE;
+            index = next;
+        }
+
+        elevations[index] += sediment;
+    }
+}
+
+// Material slumps from any tile onto neighbours that sit lower than the talus threshold allows
+fn thermal(neighbours: &[Vec<usize>], elevations: &mut [f64], iterations: usize) {
+    let mut deltas = vec![0.; elevations.len()];
+
+    for _ in 0..iterations {
+        for (index, neighbours) in neighbours.iter().enumerate() {
+            let height = elevations[index];
+
+            let excess = |neighbour: &usize| height - elevations[*neighbour] - TALUS_THRESHOLD;
+            let total = neighbours
+                .iter()
+                .map(excess)
+                .filter(|excess| *excess > 0.)
+                .sum::<f64>();
+
+            if total <= 0. {
+                continue;
+            }
+
+            let moved = total * THERMAL_RATE / neighbours.len() as f64;
+
+            for neighbour in neighbours {
+                let share = excess(neighbour);
+
+                if share > 0. {
+                    let amount = moved * share / total;
+                    deltas[index] -= amount;
+                    deltas[*neighbour] += amount;
+                }
+            }
+        }
+
+        for (elevation, delta) in elevations.iter_mut().zip(deltas.iter_mut()) {
+            *elevation += *delta;
+            *delta = 0.;
+        }
+    }
+}
+
+pub fn erode(
+    grid: &impl CellGraph,
+    elevations: &mut [f64],
+    ErosionParams {
+        hydraulic_iterations,
+        thermal_iterations,
+    }: ErosionParams,
+    seed: u64,
+) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let neighbours = (0..elevations.len())
+        .map(|index| grid.neighbours(index).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    hydraulic(&neighbours, elevations, hydraulic_iterations, &mut rng);
+    thermal(&neighbours, elevations, thermal_iterations);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::HexGrid;
+
+    use super::*;
+
+    fn elevations(grid: &HexGrid) -> Vec<f64> {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        (0..grid.len())
+            .map(|_| rng.random_range(-1.0..1.0))
+            .collect()
+    }
+
+    #[test]
+    fn erosion_conserves_material() {
+        let grid = HexGrid::new(32, 24);
+        let mut field = elevations(&grid);
+        let before = field.iter().sum::<f64>();
+
+        erode(&grid, &mut field, ErosionParams::new(5_000, 20), 1);
+
+        let after = field.iter().sum::<f64>();
+        assert!((before - after).abs() < 1e-9, "{before} became {after}");
+    }
+
+    #[test]
+    fn erosion_is_deterministic() {
+        let grid = HexGrid::new(32, 24);
+        let mut first = elevations(&grid);
+        let mut second = first.clone();
+
+        erode(&grid, &mut first, ErosionParams::default(), 3);
+        erode(&grid, &mut second, ErosionParams::default(), 3);
+
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{grid::CellGraph, queue::Lowest};
+
+use super::TerrainType;
+
+fn water_bodies(grid: &impl CellGraph, tiles: &[TerrainType]) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; tiles.len()];
+    let mut bodies = Vec::new();
+
+    for start in 0..tiles.len() {
+        if visited[start] || tiles[start].is_land() {
+            continue;
+        }
+
+        visited[start] = true;
+
+        let mut body = Vec::new();
+        let mut frontier = vec![start];
+
+        while let Some(index) = frontier.pop() {
+            body.push(index);
+
+            for neighbour in grid.neighbours(index) {
+                if !visited[neighbour] && !tiles[neighbour].is_land() {
+                    visited[neighbour] = true;
+                    frontier.push(neighbour);
+                }
+            }
+        }
+
+        bodies.push(body);
+    }
+
+    bodies
+}
+
+// Bodies of water larger than this share of the map are seas rather than lakes
+const MAX_LAKE_SIZE: f64 = 0.05;
+
+// Bodies of water that reach the edge of the map or are too large to be a lake are seas, part of
+// the ocean. On maps without edges the largest body always is, so there's an ocean somewhere.
+// Anything else is cut off inland, however deep, and becomes a lake.
+pub fn mark_lakes(grid: &impl CellGraph, tiles: &mut [TerrainType]) {
+    let bodies = water_bodies(grid, tiles);
+    let max_lake_size = (tiles.len() as f64 * MAX_LAKE_SIZE) as usize;
+
+    let largest = bodies
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, body)| (body.len(), Reverse(*index)))
+        .map(|(index, _)| index);
+
+    for (index, body) in bodies.into_iter().enumerate() {
+        let is_ocean = Some(index) == largest
+            || body.len() > max_lake_size
+            || body.iter().any(|index| grid.is_edge(*index));
+
+        if is_ocean {
+            continue;
+        }
+
+        for index in body {
+            tiles[index] = TerrainType::Lake;
+        }
+    }
+}
+
+// Flood inwards from the water and the edges of the map, lowest first, to find the level each
+// tile would fill up to before spilling over. Land that would sit deeper than `min_depth` below
+// that level becomes a lake.
+pub fn fill_depressions(
+    grid: &impl CellGraph,
+    tiles: &mut [TerrainType],
+    elevations: &[f64],
+    min_depth: f64,
+) {
+    let mut levels = elevations.to_vec();
+    let mut visited = vec![false; tiles.len()];
+    let mut queue = BinaryHeap::new();
+
+    for index in 0..tiles.len() {
+        if !tiles[index].is_land() || grid.is_edge(index) {
+            visited[index] = true;
+            queue.push(Lowest::new(elevations[index], index));
+        }
+    }
+
+    while let Some(Lowest {
+        value: level,
+        index,
+    }) = queue.pop()
+    {
+        for neighbour in grid.neighbours(index) {
+            if visited[neighbour] {
+                continue;
+            }
+
+            visited[neighbour] = true;
+            levels[neighbour] = elevations[neighbour].max(level);
+            queue.push(Lowest::new(levels[neighbour], neighbour));
+        }
+    }
+
+    for (index, tile) in tiles.iter_mut().enumerate() {
+        if tile.is_land() && levels[index] - elevations[index] > min_depth {
+            *tile = TerrainType::Lake;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{HexGrid, Topology};
+
+    use super::*;
+
+    // Land everywhere but the cells picked out, which are deep water
+    fn tiles(grid: &HexGrid, water: impl Fn([i32; 2]) -> bool) -> Vec<TerrainType> {
+        (0..grid.len())
+            .map(|index| match water(grid.index_to_cell(index)) {
+                true => TerrainType::DeepOcean,
+                false => TerrainType::Plains,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn enclosed_water_becomes_lakes() {
+        let grid = HexGrid::new(30, 20).with_topology(Topology::Flat);
+
+        // An ocean along the left edge, a smaller sea along the right edge and a deep basin in
+        // the middle, all kept apart by land
+        let mut tiles = tiles(&grid, |[x, y]| {
+            x <= 8 || x >= 27 || ((14..=18).contains(&x) && (8..=12).contains(&y))
+        });
+
+        mark_lakes(&grid, &mut tiles);
+
+        for (index, tile) in tiles.iter().enumerate() {
+            match grid.index_to_cell(index) {
+                [..=8, _] | [27..=30, _] => assert_eq!(*tile, TerrainType::DeepOcean),
+                [14..=18, 8..=12] => assert_eq!(*tile, TerrainType::Lake),
+                _ => assert_eq!(*tile, TerrainType::Plains),
+            }
+        }
+    }
+
+    #[test]
+    fn large_bodies_are_seas_without_edges() {
+        let grid = HexGrid::new(30, 20).with_topology(Topology::Torus);
+
+        // The largest body, another too large to be a lake and a small basin
+        let mut tiles = tiles(&grid, |[x, y]| {
+            (3..=10).contains(&x)
+                || (14..=16).contains(&x)
+                || ((21..=25).contains(&x) && (8..=12).contains(&y))
+        });
+
+        mark_lakes(&grid, &mut tiles);
+
+        for (index, tile) in tiles.iter().enumerate() {
+            match grid.index_to_cell(index) {
+                [3..=10, _] | [14..=16, _] => assert_eq!(*tile, TerrainType::DeepOcean),
+                [21..=25, 8..=12] => assert_eq!(*tile, TerrainType::Lake),
+                _ => assert_eq!(*tile, TerrainType::Plains),
+            }
+        }
+    }
+}
@@ -1,97 +1,226 @@
-use hexx::HexLayout;
-use rand::{Rng, rng};
+use hexx::{HexLayout, HexOrientation, OffsetHexMode};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-use crate::cylinder::CylindricalHexMapSampler;
+use crate::{
+    heightmap::{self, Heightmap},
+    sampler::HexMapSampler,
+};
 
 use self::noise::NoiseParameters;
 
-pub use self::world::{GeneratedTerrain, TerrainType};
+pub use crate::grid::{HexGrid, Topology};
 
-mod noise;
+pub use self::{
+    classifier::{ClassifierError, TerrainBand, TerrainClassifier},
+    climate::{Biome, Climate},
+    erosion::ErosionParams,
+    tectonics::ElevationMode,
+    world::{GeneratedTerrain, TerrainType},
+};
+
+mod classifier;
+mod tectonics;
 mod world;
 
-#[derive(Debug, Clone, Copy)]
+pub(crate) mod climate;
+pub(crate) mod erosion;
+pub(crate) mod lakes;
+pub(crate) mod noise;
+pub(crate) mod polar;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TerrainParams {
     width: i32,
     height: i32,
     scale_factor: f64,
+    seed: u64,
+    classifier: TerrainClassifier,
+    depression_depth: Option<f64>,
+    polar_band: i32,
+    erosion: Option<ErosionParams>,
+    elevation_mode: ElevationMode,
+    orientation: HexOrientation,
+    offset_mode: OffsetHexMode,
+    topology: Topology,
 }
 
 impl TerrainParams {
-    pub fn new(width: i32, height: i32, scale_factor: f64) -> Self {
+    pub fn new(width: i32, height: i32, scale_factor: f64, seed: u64) -> Self {
         Self {
             width,
             height,
             scale_factor,
+            seed,
+            classifier: TerrainClassifier::default(),
+            depression_depth: None,
+            polar_band: 3,
+            erosion: None,
+            elevation_mode: ElevationMode::Noise,
+            orientation: HexOrientation::Flat,
+            offset_mode: OffsetHexMode::Even,
+            topology: Topology::Cylinder,
         }
     }
-}
 
-fn get_terrain(value: f64) -> TerrainType {
-    let to_range = |value| (value / 1.67) - 0.4;
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 
-    let value = to_range(value + 1.);
+    pub fn with_classifier(self, classifier: TerrainClassifier) -> Self {
+        Self { classifier, ..self }
+    }
 
-    let ocean_percentage = 0.44;
-    let mountain_percentage = 0.32;
+    pub fn with_depression_filling(self, min_depth: f64) -> Self {
+        Self {
+            depression_depth: Some(min_depth),
+            ..self
+        }
+    }
 
-    let land_threshold = to_range(2. * ocean_percentage);
-    let mountain_threshold = to_range(2. * (1. - mountain_percentage));
+    pub fn with_polar_band(self, rows: i32) -> Self {
+        Self {
+            polar_band: rows,
+            ..self
+        }
+    }
 
-    if value <= land_threshold {
-        let percentage = value / land_threshold;
+    pub fn with_erosion(self, erosion: ErosionParams) -> Self {
+        Self {
+            erosion: Some(erosion),
+            ..self
+        }
+    }
 
-        if percentage < 0.3 {
-            TerrainType::DeepOcean
-        } else if percentage < 0.8 {
-            TerrainType::ShallowOcean
-        } else {
-            TerrainType::Coast
+    pub fn with_elevation_mode(self, elevation_mode: ElevationMode) -> Self {
+        Self {
+            elevation_mode,
+            ..self
         }
-    } else if value <= mountain_threshold {
-        let percentage = (value - land_threshold) / (mountain_threshold - land_threshold);
-
-        if percentage < 0.085 {
-            TerrainType::Beach
-        } else if percentage < 0.6 {
-            TerrainType::Plains
-        } else {
-            TerrainType::Hills
+    }
+
+    pub fn with_orientation(self, orientation: HexOrientation) -> Self {
+        Self {
+            orientation,
+            ..self
         }
-    } else {
-        let percentage = (value - mountain_threshold) / (0.6 - mountain_threshold);
-
-        if percentage < 0.25 {
-            TerrainType::LowMountains
-        } else if percentage < 0.6 {
-            TerrainType::HighMountains
-        } else {
-            TerrainType::Peaks
+    }
+
+    pub fn with_offset_mode(self, offset_mode: OffsetHexMode) -> Self {
+        Self {
+            offset_mode,
+            ..self
         }
     }
+
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
+    }
+}
+
+pub fn generate(params: TerrainParams) -> GeneratedTerrain {
+    build(params, None)
+}
+
+// Takes the elevation from a heightmap stretched over the map rather than from noise, leaving the
+// elevation mode unused. Everything after that runs as usual.
+pub fn generate_from_heightmap(params: TerrainParams, heightmap: &Heightmap) -> GeneratedTerrain {
+    build(params, Some(heightmap))
 }
 
-pub fn generate(
+fn build(
     TerrainParams {
         width,
         height,
         scale_factor,
+        seed,
+        classifier,
+        depression_depth,
+        polar_band,
+        erosion,
+        elevation_mode,
+        orientation,
+        offset_mode,
+        topology,
     }: TerrainParams,
+    heightmap: Option<&Heightmap>,
 ) -> GeneratedTerrain {
-    let layout = HexLayout::flat().with_hex_size(2.);
-    let sampler = CylindricalHexMapSampler::new(width, height, scale_factor, layout.clone());
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let grid = HexGrid::new(width, height)
+        .with_orientation(orientation, offset_mode)
+        .with_topology(topology);
+    let layout = HexLayout::new(orientation).with_hex_size(2.);
+    let sampler = HexMapSampler::new(grid, scale_factor, layout.clone());
 
     let noise = noise::get_noise_fn(NoiseParameters::new(
-        rng().random(),
+        rng.random(),
         sampler.height_extent(),
         sampler.y_step() * 15.,
         0.6,
     ));
 
-    GeneratedTerrain::new(
-        width,
-        height,
-        layout,
-        sampler.generate(noise).map(get_terrain).collect(),
-    )
+    let moisture = climate::get_moisture_fn(rng.random());
+    let temperature = climate::get_temperature_fn(rng.random(), sampler.height_extent());
+    let ice = polar::get_ice_fn(rng.random());
+
+    let mut elevations = if let Some(heightmap) = heightmap {
+        let positions = (0..grid.len())
+            .map(|index| layout.hex_to_world_pos(grid.index_to_hex(index)))
+            .collect::<Vec<_>>();
+
+        heightmap::elevations(heightmap, &positions)
+    } else {
+        match elevation_mode {
+            ElevationMode::Noise => sampler.generate(noise).collect(),
+            ElevationMode::Tectonic { plates } => {
+                tectonics::generate(&grid, &layout, &sampler, plates, rng.random())
+            }
+            ElevationMode::Blend { plates, weight } => {
+                tectonics::generate(&grid, &layout, &sampler, plates, rng.random())
+                    .into_iter()
+                    .zip(sampler.generate(noise))
+                    .map(|(tectonic, noise)| noise * (1. - weight) + tectonic * weight)
+                    .collect()
+            }
+        }
+    };
+
+    if let Some(erosion) = erosion {
+        erosion::erode(&grid, &mut elevations, erosion, rng.random());
+    }
+
+    let mut tiles = elevations
+        .iter()
+        .map(|value| classifier.classify(*value))
+        .collect::<Vec<_>>();
+
+    lakes::mark_lakes(&grid, &mut tiles);
+
+    if let Some(min_depth) = depression_depth {
+        lakes::fill_depressions(&grid, &mut tiles, &elevations, min_depth);
+    }
+
+    // The top and bottom of a torus join up, so there are no poles to freeze
+    if !topology.wraps_y() {
+        polar::apply_ice_caps(
+            &mut tiles,
+            polar_band as f64,
+            polar::row_distances(&grid),
+            sampler.generate(ice),
+        );
+    }
+
+    let climate = tiles
+        .iter()
+        .zip(
+            sampler
+                .generate(temperature)
+                .zip(sampler.generate(moisture)),
+        )
+        .map(|(tile, (temperature, moisture))| Climate::new(*tile, temperature, moisture))
+        .collect();
+
+    GeneratedTerrain::new(grid, layout, tiles, elevations, climate)
 }
@@ -1,5 +1,7 @@
 use noise::{Add, Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti, ScaleBias, Seedable};
 
+use crate::sampler::Dimensions;
+
 pub struct NoiseParameters {
     seed: u32,
     height_extent: f64,
@@ -25,7 +27,7 @@ pub fn get_noise_fn(
         continent_bounds,
         erosion_power,
     }: NoiseParameters,
-) -> impl NoiseFn<f64, 3> {
+) -> impl NoiseFn<f64, 3> + NoiseFn<f64, 4> {
     let base = Fbm::<Perlin>::default()
         .set_seed(seed)
         .set_lacunarity(1.91010101)
@@ -33,26 +35,38 @@ pub fn get_noise_fn(
         .set_octaves(12);
 
     let ridged = RidgedMulti::<Perlin>::default()
-        .set_seed(seed + 1)
+        .set_seed(seed.wrapping_add(1))
         .set_frequency(0.9)
         .set_lacunarity(2.11010101)
         .set_persistence(0.60)
         .set_octaves(5);
 
-    let added = Add::new(
-        ScaleBias::new(base).set_scale(0.7),
-        ScaleBias::new(ridged).set_scale(0.3),
-    );
-
     ContinentWrapper {
         overall_extent: height_extent,
         bounds_extent: continent_bounds,
         erosion_power,
-        source: added,
+        source: Dimensions(
+            combine::<3>(base.clone(), ridged.clone()),
+            combine::<4>(base, ridged),
+        ),
     }
 }
 
-struct ContinentWrapper<Source: NoiseFn<f64, 3>> {
+fn combine<const DIM: usize>(
+    base: Fbm<Perlin>,
+    ridged: RidgedMulti<Perlin>,
+) -> impl NoiseFn<f64, DIM>
+where
+    Fbm<Perlin>: NoiseFn<f64, DIM>,
+    RidgedMulti<Perlin>: NoiseFn<f64, DIM>,
+{
+    Add::new(
+        ScaleBias::new(base).set_scale(0.7),
+        ScaleBias::new(ridged).set_scale(0.3),
+    )
+}
+
+struct ContinentWrapper<Source> {
     overall_extent: f64,
     bounds_extent: f64,
     erosion_power: f64,
@@ -60,11 +74,8 @@ struct ContinentWrapper<Source: NoiseFn<f64, 3>> {
     source: Source,
 }
 
-impl<T: NoiseFn<f64, 3>> NoiseFn<f64, 3> for ContinentWrapper<T> {
-    fn get(&self, point: [f64; 3]) -> f64 {
-        let inner = self.source.get(point);
-
-        let y = point[1];
+impl<T> ContinentWrapper<T> {
+    fn apply(&self, inner: f64, y: f64) -> f64 {
         let mut erosion = 0.;
 
         if y < self.bounds_extent {
@@ -80,3 +91,17 @@ impl<T: NoiseFn<f64, 3>> NoiseFn<f64, 3> for ContinentWrapper<T> {
         inner - erosion
     }
 }
+
+impl<T: NoiseFn<f64, 3>> NoiseFn<f64, 3> for ContinentWrapper<T> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        self.apply(self.source.get(point), point[1])
+    }
+}
+
+// Only toroidal maps are sampled in 4D, and they have no top or bottom edge for the land to fall
+// away into the sea at
+impl<T: NoiseFn<f64, 4>> NoiseFn<f64, 4> for ContinentWrapper<T> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        self.source.get(point)
+    }
+}
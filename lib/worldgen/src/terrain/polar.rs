@@ -0,0 +1,45 @@
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin, Seedable};
+
+use crate::grid::HexGrid;
+
+use super::TerrainType;
+
+pub fn get_ice_fn(seed: u32) -> impl NoiseFn<f64, 3> + NoiseFn<f64, 4> {
+    Fbm::<Perlin>::default()
+        .set_seed(seed)
+        .set_frequency(4.)
+        .set_octaves(3)
+}
+
+// How many rows each tile is from the nearest of the top and bottom of the map
+pub fn row_distances(grid: &HexGrid) -> impl Iterator<Item = f64> {
+    (0..grid.len()).map(|index| {
+        let row = index as i32 % grid.height();
+        row.min(grid.height() - 1 - row) as f64
+    })
+}
+
+// Freezes everything within `band` of the nearest pole, with the edge of the ice pushed back and
+// forth by up to half the band so it doesn't follow a straight line
+pub fn apply_ice_caps(
+    tiles: &mut [TerrainType],
+    band: f64,
+    distances: impl Iterator<Item = f64>,
+    jitter: impl Iterator<Item = f64>,
+) {
+    if band <= 0. {
+        return;
+    }
+
+    for ((tile, distance), jitter) in tiles.iter_mut().zip(distances).zip(jitter) {
+        let threshold = band * (1. + jitter.clamp(-1., 1.) * 0.5);
+
+        if distance < threshold {
+            *tile = if tile.is_land() {
+                TerrainType::Glacier
+            } else {
+                TerrainType::SeaIce
+            };
+        }
+    }
+}
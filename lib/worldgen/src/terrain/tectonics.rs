@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use hexx::{Hex, HexLayout};
+use noise::Perlin;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{grid::HexGrid, sampler::HexMapSampler};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElevationMode {
+    Noise,
+    Tectonic { plates: usize },
+    // Mixes the two sources, with `weight` being the share taken from the plates
+    Blend { plates: usize, weight: f64 },
+}
+
+struct Plate {
+    centre: [f64; 4],
+    motion: [f64; 2],
+    base: f64,
+    continental: bool,
+}
+
+const WARP_STRENGTH: f64 = 0.35;
+const BOUNDARY_FALLOFF: f64 = 4.;
+const BOUNDARY_STRENGTH: f64 = 0.6;
+
+fn distance_squared(a: [f64; 4], b: [f64; 4]) -> f64 {
+    (0..4).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+fn random_plate(rng: &mut impl Rng, centre: [f64; 4]) -> Plate {
+    let angle = rng.random_range(0.0..std::f64::consts::TAU);
+    let speed = rng.random_range(0.5..1.);
+    let continental = rng.random_bool(0.45);
+
+    Plate {
+        centre,
+        motion: [libm::cos(angle) * speed, libm::sin(angle) * speed],
+        base: if continental {
+            rng.random_range(0.0..0.25)
+        } else {
+            rng.random_range(-0.6..-0.3)
+        },
+        continental,
+    }
+}
+
+// Assigns every tile to the plate with the nearest centre, after warping the tile's position so
+// that the boundaries between plates aren't straight lines
+fn assign_plates(
+    sampler: &HexMapSampler,
+    points: &[[f64; 4]],
+    plates: &[Plate],
+    seed: u32,
+) -> Vec<usize> {
+    let warp = [0, 1, 2, 3].map(|offset| Perlin::new(seed.wrapping_add(offset)));
+
+    points
+        .iter()
+        .map(|point| {
+            let mut warped = *point;
+
+            for axis in 0..sampler.dimensions() {
+                warped[axis] += sampler.sample(*point, &warp[axis]) * WARP_STRENGTH;
+            }
+
+            (0..plates.len())
+                .min_by(|a, b| {
+                    distance_squared(warped, plates[*a].centre)
+                        .total_cmp(&distance_squared(warped, plates[*b].centre))
+                })
+                .unwrap()
+        })
+        .collect()
+}
+
+// How strongly each boundary tile is being pushed together (positive) or pulled apart
+// (negative) by the relative motion of the plates on either side
+fn boundary_stress(
+    grid: &HexGrid,
+    layout: &HexLayout,
+    plates: &[Plate],
+    assignments: &[usize],
+) -> Vec<Option<f64>> {
+    (0..grid.len())
+        .map(|index| {
+            let plate = &plates[assignments[index]];
+
+            let stresses = grid
+                .neighbour_directions(index)
+                .filter(|(_, neighbour)| assignments[*neighbour] != assignments[index])
+                .map(|(direction, neighbour)| {
+                    let other = &plates[assignments[neighbour]];
+                    let normal = layout
+                        .hex_to_world_pos(Hex::ZERO.neighbor(direction))
+                        .normalize();
+
+                    let convergence = (plate.motion[0] - other.motion[0]) * normal.x as f64
+                        + (plate.motion[1] - other.motion[1]) * normal.y as f64;
+
+                    // Oceanic plates dive under continental ones, leaving a trench on their side
+                    if convergence > 0. && !plate.continental && other.continental {
+                        -convergence * 0.5
+                    } else {
+                        convergence
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            (!stresses.is_empty()).then(|| stresses.iter().sum::<f64>() / stresses.len() as f64)
+        })
+        .collect()
+}
+
+pub fn generate(
+    grid: &HexGrid,
+    layout: &HexLayout,
+    sampler: &HexMapSampler,
+    plate_count: usize,
+    seed: u64,
+) -> Vec<f64> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let points = sampler.points().collect::<Vec<_>>();
+
+    let plates = (0..plate_count.max(1))
+        .map(|_| {
+            let centre = points[rng.random_range(0..points.len())];
+            random_plate(&mut rng, centre)
+        })
+        .collect::<Vec<_>>();
+
+    let assignments = assign_plates(sampler, &points, &plates, rng.random());
+    let stress = boundary_stress(grid, layout, &plates, &assignments);
+
+    // Spread the effect of each boundary out to the tiles around it, fading with distance
+    let mut nearest = stress
+        .iter()
+        .map(|stress| stress.map(|stress| (stress, 0)))
+        .collect::<Vec<_>>();
+
+    let mut frontier = (0..grid.len())
+        .filter(|index| nearest[*index].is_some())
+        .collect::<VecDeque<_>>();
+
+    while let Some(index) = frontier.pop_front() {
+        let (stress, distance) = nearest[index].unwrap();
+
+        for neighbour in grid.neighbours(index) {
+            if nearest[neighbour].is_none() {
+                nearest[neighbour] = Some((stress, distance + 1));
+                frontier.push_back(neighbour);
+            }
+        }
+    }
+
+    assignments
+        .iter()
+        .zip(nearest)
+        .map(|(plate, nearest)| {
+            let boundary = nearest.map_or(0., |(stress, distance)| {
+                let falloff = distance as f64 / BOUNDARY_FALLOFF;
+                stress * libm::exp(-falloff * falloff)
+            });
+
+            plates[*plate].base + boundary * BOUNDARY_STRENGTH
+        })
+        .collect()
+}
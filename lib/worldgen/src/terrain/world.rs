@@ -1,81 +1,272 @@
 use hexx::{Hex, HexLayout, HexOrientation, OffsetHexMode};
 
-#[derive(Debug, Clone, Copy)]
+use crate::grid::{HexGrid, Topology};
+
+use super::{Biome, Climate};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TerrainType {
     DeepOcean,
     ShallowOcean,
     Coast,
+    Lake,
     Beach,
     Plains,
     Hills,
     LowMountains,
     HighMountains,
     Peaks,
+    SeaIce,
+    Glacier,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GeneratedTerrain {
-    width: i32,
-    height: i32,
+    grid: HexGrid,
     layout: HexLayout,
     tiles: Vec<TerrainType>,
+    elevations: Vec<f64>,
+    climate: Vec<Climate>,
 }
 
 impl TerrainType {
+    pub const ALL: [TerrainType; 12] = [
+        TerrainType::DeepOcean,
+        TerrainType::ShallowOcean,
+        TerrainType::Coast,
+        TerrainType::Lake,
+        TerrainType::Beach,
+        TerrainType::Plains,
+        TerrainType::Hills,
+        TerrainType::LowMountains,
+        TerrainType::HighMountains,
+        TerrainType::Peaks,
+        TerrainType::SeaIce,
+        TerrainType::Glacier,
+    ];
+
     pub fn is_habitable(&self) -> bool {
         match self {
             TerrainType::DeepOcean => false,
             TerrainType::ShallowOcean => false,
             TerrainType::Coast => false,
+            TerrainType::Lake => false,
             TerrainType::Beach => false,
             TerrainType::Plains => true,
             TerrainType::Hills => true,
             TerrainType::LowMountains => false,
             TerrainType::HighMountains => false,
             TerrainType::Peaks => false,
+            TerrainType::SeaIce => false,
+            TerrainType::Glacier => false,
+        }
+    }
+
+    pub fn is_land(&self) -> bool {
+        match self {
+            TerrainType::DeepOcean => false,
+            TerrainType::ShallowOcean => false,
+            TerrainType::Coast => false,
+            TerrainType::Lake => false,
+            TerrainType::Beach => true,
+            TerrainType::Plains => true,
+            TerrainType::Hills => true,
+            TerrainType::LowMountains => true,
+            TerrainType::HighMountains => true,
+            TerrainType::Peaks => true,
+            TerrainType::SeaIce => false,
+            TerrainType::Glacier => true,
         }
     }
 }
 
 impl GeneratedTerrain {
-    pub fn new(width: i32, height: i32, layout: HexLayout, tiles: Vec<TerrainType>) -> Self {
+    pub fn new(
+        grid: HexGrid,
+        layout: HexLayout,
+        tiles: Vec<TerrainType>,
+        elevations: Vec<f64>,
+        climate: Vec<Climate>,
+    ) -> Self {
+        assert_eq!(tiles.len(), grid.len());
+        assert_eq!(tiles.len(), elevations.len());
+        assert_eq!(tiles.len(), climate.len());
+
         Self {
-            width,
-            height,
+            grid,
             layout,
             tiles,
+            elevations,
+            climate,
         }
     }
 
+    // Terrain read back from a file hasn't been through `new`, so its tiles may not cover the grid
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_complete(&self) -> bool {
+        let len = self.grid.len();
+
+        self.grid.width() > 0
+            && self.grid.height() > 0
+            && self.tiles.len() == len
+            && self.elevations.len() == len
+            && self.climate.len() == len
+    }
+
     pub fn width(&self) -> i32 {
-        self.width
+        self.grid.width()
     }
 
     pub fn height(&self) -> i32 {
-        self.height
+        self.grid.height()
+    }
+
+    pub fn orientation(&self) -> HexOrientation {
+        self.grid.orientation()
+    }
+
+    pub fn offset_mode(&self) -> OffsetHexMode {
+        self.grid.offset_mode()
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.grid.topology()
     }
 
     pub fn layout(&self) -> &HexLayout {
         &self.layout
     }
 
-    fn index_to_hex(height: i32, index: usize) -> Hex {
-        let x = (index as i32 / height) + 1;
-        let mut y = (index as i32 % height) + 1;
+    pub fn grid(&self) -> HexGrid {
+        self.grid
+    }
 
-        if x % 2 == 1 {
-            y += 1
-        }
+    pub fn wrap(&self, hex: Hex) -> Hex {
+        self.grid.wrap(hex)
+    }
+
+    // Where a hex sits in the tiles once wrapped onto the map, so every lookup treats the copies
+    // of a hex beyond a seam as the hex itself
+    fn index(&self, hex: Hex) -> Option<usize> {
+        self.grid.hex_to_index(self.grid.wrap(hex))
+    }
+
+    pub fn contains(&self, hex: Hex) -> bool {
+        self.index(hex).is_some()
+    }
+
+    pub fn get(&self, hex: Hex) -> Option<TerrainType> {
+        self.index(hex).map(|index| self.tiles[index])
+    }
+
+    // Looks up a tile by its column and row, counting from 1 at the top left
+    pub fn get_offset(&self, x: i32, y: i32) -> Option<TerrainType> {
+        self.get(self.grid.cell_to_hex([x, y]))
+    }
+
+    // The neighbours of a hex that are on the map, wrapped across any seams
+    pub fn neighbors(&self, hex: Hex) -> impl Iterator<Item = Hex> {
+        let grid = self.grid;
 
-        Hex::from_offset_coordinates([x, y], OffsetHexMode::Even, HexOrientation::Flat)
+        hex.all_neighbors()
+            .into_iter()
+            .map(move |neighbour| grid.wrap(neighbour))
+            .filter(move |neighbour| grid.hex_to_index(*neighbour).is_some())
     }
 
     pub fn tiles(&self) -> impl Iterator<Item = (Hex, TerrainType)> {
-        let height = self.height;
+        let grid = self.grid;
 
         self.tiles
             .iter()
             .enumerate()
-            .map(move |(index, tile)| (Self::index_to_hex(height, index), *tile))
+            .map(move |(index, tile)| (grid.index_to_hex(index), *tile))
+    }
+
+    pub fn elevation(&self, hex: Hex) -> Option<f64> {
+        self.index(hex).map(|index| self.elevations[index])
+    }
+
+    pub fn elevations(&self) -> impl Iterator<Item = (Hex, f64)> {
+        let grid = self.grid;
+
+        self.elevations
+            .iter()
+            .enumerate()
+            .map(move |(index, elevation)| (grid.index_to_hex(index), *elevation))
+    }
+
+    pub fn climate(&self, hex: Hex) -> Option<Climate> {
+        self.index(hex).map(|index| self.climate[index])
+    }
+
+    pub fn climates(&self) -> impl Iterator<Item = (Hex, Climate)> {
+        let grid = self.grid;
+
+        self.climate
+            .iter()
+            .enumerate()
+            .map(move |(index, climate)| (grid.index_to_hex(index), *climate))
+    }
+
+    pub fn biome(&self, hex: Hex) -> Option<Biome> {
+        self.climate(hex).and_then(|climate| climate.biome)
+    }
+
+    pub fn biomes(&self) -> impl Iterator<Item = (Hex, Option<Biome>)> {
+        self.climates().map(|(hex, climate)| (hex, climate.biome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams, Topology};
+
+    #[test]
+    fn lookups_wrap_across_the_seam() {
+        let terrain = terrain::generate(TerrainParams::new(20, 12, 1.2, 3));
+        let grid = terrain.grid();
+
+        let west = grid.cell_to_hex([1, 6]);
+        let east = grid.cell_to_hex([20, 6]);
+
+        assert_eq!(terrain.get_offset(21, 6), terrain.get(west));
+        assert!(terrain.neighbors(west).any(|hex| hex == east));
+        assert!(terrain.neighbors(east).any(|hex| hex == west));
+
+        // Cylinders end at the top and bottom
+        let top = grid.cell_to_hex([5, 1]);
+
+        assert!(terrain.neighbors(top).count() < 6);
+        assert!(!terrain.contains(grid.cell_to_hex([5, 0])));
+
+        let torus =
+            terrain::generate(TerrainParams::new(20, 12, 1.2, 3).with_topology(Topology::Torus));
+
+        assert_eq!(torus.neighbors(top).count(), 6);
+        assert!(torus.contains(grid.cell_to_hex([5, 0])));
+    }
+
+    #[test]
+    fn tori_have_no_edge_along_the_top_and_bottom_seam() {
+        for seed in [1, 2, 3] {
+            let terrain = terrain::generate(
+                TerrainParams::new(80, 48, 1.2, seed).with_topology(Topology::Torus),
+            );
+            let grid = terrain.grid();
+
+            // Neither side of the seam is left as a strip of sea or ice
+            for y in [1, 48] {
+                assert!((1..=80).any(|x| {
+                    terrain
+                        .get(grid.cell_to_hex([x, y]))
+                        .is_some_and(|tile| tile.is_land() && tile != TerrainType::Glacier)
+                }));
+            }
+        }
     }
 }
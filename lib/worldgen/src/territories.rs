@@ -0,0 +1,170 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use hexx::Hex;
+
+use crate::{
+    grid::HexGrid,
+    pathfinding::{self, TravelCost},
+    terrain::GeneratedTerrain,
+};
+
+// The settlement a hex belongs to, and how much it costs to reach its seat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Territory {
+    pub zone: usize,
+    pub cost: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Territories {
+    grid: HexGrid,
+    claims: Vec<Option<Territory>>,
+}
+
+impl Territories {
+    pub fn get(&self, hex: Hex) -> Option<Territory> {
+        self.grid
+            .hex_to_index(self.grid.wrap(hex))
+            .and_then(|index| self.claims[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Hex, Territory)> {
+        let grid = self.grid;
+
+        self.claims
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, claim)| claim.map(|claim| (grid.index_to_hex(index), claim)))
+    }
+
+    // Each hex paired with the index of the settlement whose zone it falls in
+    pub fn zones(&self) -> impl Iterator<Item = (Hex, usize)> {
+        self.iter().map(|(hex, territory)| (hex, territory.zone))
+    }
+}
+
+// Climbing onto costlier ground costs in full, while heading down onto cheaper or equally costly
+// ground costs half as much, so zones spread easily along valleys and coasts
+fn step_cost(from: usize, to: usize) -> usize {
+    if to > from { to } else { to / 2 }
+}
+
+// Spreads a zone out from each settlement, giving every hex to whichever settlement can reach it
+// most cheaply. Ties go to the settlement that comes first.
+pub fn generate(
+    terrain: &GeneratedTerrain,
+    settlements: &[Hex],
+    cost: impl TravelCost,
+) -> Territories {
+    let grid = terrain.grid();
+    let costs = pathfinding::entry_costs(terrain, &cost);
+
+    let mut claims = vec![None; grid.len()];
+    let mut queue = BinaryHeap::new();
+
+    for (zone, hex) in settlements.iter().enumerate() {
+        let Some(index) = grid.hex_to_index(grid.wrap(*hex)) else {
+            continue;
+        };
+
+        if claims[index].is_none() {
+            claims[index] = Some(Territory { zone, cost: 0 });
+            queue.push(Reverse((0, zone, index)));
+        }
+    }
+
+    while let Some(Reverse((cost, zone, index))) = queue.pop() {
+        // Skip entries left behind when a better claim was found
+        if claims[index] != Some(Territory { zone, cost }) {
+            continue;
+        }
+
+        let here = costs[index].unwrap_or(0);
+
+        for neighbour in grid.neighbours(index) {
+            let Some(entry) = costs[neighbour] else {
+                continue;
+            };
+
+            let claim = Territory {
+                zone,
+                cost: cost + step_cost(here, entry),
+            };
+
+            if claims[neighbour].is_none_or(|existing: Territory| {
+                (claim.cost, zone) < (existing.cost, existing.zone)
+            }) {
+                claims[neighbour] = Some(claim);
+                queue.push(Reverse((claim.cost, zone, neighbour)));
+            }
+        }
+    }
+
+    Territories { grid, claims }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        pathfinding::ZoneCost,
+        terrain::{self, Biome, TerrainParams, TerrainType},
+    };
+
+    #[test]
+    fn hexes_go_to_the_nearest_settlement() {
+        let terrain = terrain::generate(TerrainParams::new(30, 20, 1.2, 4));
+        let grid = terrain.grid();
+
+        // Even ground costs half of every step, so the cost to the seat is the distance to it
+        let even = |_: TerrainType, _: Option<Biome>| Some(2);
+
+        let settlements = [[3, 10], [15, 4], [15, 16], [28, 10]].map(|cell| grid.cell_to_hex(cell));
+        let territories = generate(&terrain, &settlements, even);
+
+        for (hex, territory) in territories.iter() {
+            let (zone, distance) = settlements
+                .iter()
+                .map(|seat| grid.distance(hex, *seat))
+                .enumerate()
+                .min_by_key(|(zone, distance)| (*distance, *zone))
+                .unwrap();
+
+            assert_eq!(territory.cost, distance as usize);
+            assert_eq!(territory.zone, zone);
+        }
+
+        assert_eq!(territories.iter().count(), grid.len());
+    }
+
+    #[test]
+    fn every_claim_is_the_cheapest_step_from_a_neighbour() {
+        let terrain = terrain::generate(TerrainParams::new(40, 24, 1.2, 9));
+        let grid = terrain.grid();
+
+        let settlements = [[5, 5], [20, 12], [35, 20]].map(|cell| grid.cell_to_hex(cell));
+        let territories = generate(&terrain, &settlements, ZoneCost);
+
+        let cost =
+            |hex| pathfinding::terrain_zone_cost(terrain.get(hex).unwrap(), terrain.biome(hex));
+
+        for (hex, territory) in territories.iter() {
+            if settlements.contains(&hex) {
+                assert_eq!(territory.cost, 0);
+                continue;
+            }
+
+            let best = terrain
+                .neighbors(hex)
+                .map(|neighbour| {
+                    let from = territories.get(neighbour).unwrap();
+                    (from.cost + step_cost(cost(neighbour), cost(hex)), from.zone)
+                })
+                .min()
+                .unwrap();
+
+            assert_eq!((territory.cost, territory.zone), best);
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use hexx::{Hex, HexLayout, HexOrientation, Vec2};
+use serde_json::json;
+
+use crate::{
+    image::MapImage,
+    palette::terrain_colour,
+    terrain::{GeneratedTerrain, TerrainType, Topology},
+};
+
+const FORMAT_VERSION: &str = "1.10";
+const TILESET_NAME: &str = "terrain";
+
+#[derive(Debug, Clone)]
+pub struct TiledParams {
+    hex_size: u32,
+    settlements: Vec<(Hex, String)>,
+}
+
+impl TiledParams {
+    // Tiles are drawn with sides `hex_size` pixels long
+    pub fn new(hex_size: u32) -> Self {
+        Self {
+            hex_size: hex_size.max(2),
+            settlements: Vec::new(),
+        }
+    }
+
+    pub fn with_settlements<Name: Into<String>>(
+        self,
+        settlements: impl IntoIterator<Item = (Hex, Name)>,
+    ) -> Self {
+        Self {
+            settlements: settlements
+                .into_iter()
+                .map(|(hex, name)| (hex, name.into()))
+                .collect(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    orientation: HexOrientation,
+    topology: Topology,
+    width: i32,
+    height: i32,
+    tile_size: [u32; 3],
+    stagger_even: bool,
+    // Indices into `TerrainType::ALL`, row by row from the top left
+    tiles: Vec<usize>,
+    settlements: Vec<(String, Vec2)>,
+    tileset: MapImage,
+}
+
+// Where Tiled draws the centre of a tile, given the width, height and side length of its tiles.
+// Staggered columns (or rows, for pointy-top maps) are pushed half a tile down (or right).
+fn tile_centre(
+    orientation: HexOrientation,
+    stagger_even: bool,
+    [width, height, side]: [f32; 3],
+    [column, row]: [i32; 2],
+) -> Vec2 {
+    let shift = |line: i32| {
+        if (line.rem_euclid(2) == 0) == stagger_even {
+            0.5
+        } else {
+            0.
+        }
+    };
+
+    match orientation {
+        HexOrientation::Flat => Vec2::new(
+            column as f32 * (width + side) / 2. + width / 2.,
+            (row as f32 + 0.5 + shift(column)) * height,
+        ),
+        HexOrientation::Pointy => Vec2::new(
+            (column as f32 + 0.5 + shift(row)) * width,
+            row as f32 * (height + side) / 2. + height / 2.,
+        ),
+    }
+}
+
+// Tiled counts rows down the screen, so works out whether the grid's rows need flipping to match,
+// and which lines end up staggered once they are
+fn tiled_layout(terrain: &GeneratedTerrain) -> (bool, bool) {
+    let grid = terrain.grid();
+    let layout = terrain.layout();
+
+    let screen = |cell: [i32; 2]| {
+        let position = layout.hex_to_world_pos(grid.cell_to_hex(cell));
+        Vec2::new(position.x, -position.y)
+    };
+
+    let flip_rows = screen([1, 2]).y < screen([1, 1]).y;
+
+    let cell = |column: i32, row: i32| {
+        [
+            column + 1,
+            if flip_rows {
+                grid.height() - row
+            } else {
+                row + 1
+            },
+        ]
+    };
+
+    let stagger_even = match grid.orientation() {
+        HexOrientation::Flat => screen(cell(0, 0)).y > screen(cell(1, 0)).y,
+        HexOrientation::Pointy => screen(cell(0, 0)).x > screen(cell(0, 1)).x,
+    };
+
+    (flip_rows, stagger_even)
+}
+
+fn tileset_image(orientation: HexOrientation, [width, height, _]: [u32; 3]) -> MapImage {
+    let sqrt_3 = 3f32.sqrt();
+
+    // Squash the hex very slightly so it exactly fills the whole-pixel tile
+    let mut layout = HexLayout::new(orientation);
+    layout.scale = match orientation {
+        HexOrientation::Flat => Vec2::new(width as f32 / 2., height as f32 / sqrt_3),
+        HexOrientation::Pointy => Vec2::new(width as f32 / sqrt_3, height as f32 / 2.),
+    };
+
+    let image_width = width * TerrainType::ALL.len() as u32;
+    let mut pixels = vec![0; (image_width * height * 4) as usize];
+
+    for (tile, terrain) in TerrainType::ALL.into_iter().enumerate() {
+        let colour = terrain_colour(terrain, None);
+
+        for y in 0..height {
+            for x in 0..width {
+                let position = Vec2::new(
+                    x as f32 + 0.5 - width as f32 / 2.,
+                    height as f32 / 2. - y as f32 - 0.5,
+                );
+
+                if layout.world_pos_to_hex(position) == Hex::ZERO {
+                    let index = ((y * image_width + tile as u32 * width + x) * 4) as usize;
+                    pixels[index..index + 4].copy_from_slice(&colour);
+                }
+            }
+        }
+    }
+
+    MapImage::new(image_width, height, pixels)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl TiledMap {
+    // The image holding one tile for each terrain type, which has to be saved alongside the map
+    pub fn tileset(&self) -> &MapImage {
+        &self.tileset
+    }
+
+    fn stagger_axis(&self) -> &'static str {
+        match self.orientation {
+            HexOrientation::Flat => "x",
+            HexOrientation::Pointy => "y",
+        }
+    }
+
+    fn stagger_index(&self) -> &'static str {
+        if self.stagger_even { "even" } else { "odd" }
+    }
+
+    pub fn write_tmx(&self, mut writer: impl Write, tileset_source: &str) -> io::Result<()> {
+        let [tile_width, tile_height, hex_side] = self.tile_size;
+
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<map version="{FORMAT_VERSION}" orientation="hexagonal" renderorder="right-down" width="{}" height="{}" tilewidth="{tile_width}" tileheight="{tile_height}" infinite="0" hexsidelength="{hex_side}" staggeraxis="{}" staggerindex="{}" nextlayerid="3" nextobjectid="{}">"#,
+            self.width,
+            self.height,
+            self.stagger_axis(),
+            self.stagger_index(),
+            self.settlements.len() + 1,
+        )?;
+        writeln!(writer, " <properties>")?;
+        writeln!(
+            writer,
+            r#"  <property name="topology" value="{:?}"/>"#,
+            self.topology
+        )?;
+        writeln!(writer, " </properties>")?;
+        writeln!(
+            writer,
+            r#" <tileset firstgid="1" name="{TILESET_NAME}" tilewidth="{tile_width}" tileheight="{tile_height}" tilecount="{}" columns="{}">"#,
+            TerrainType::ALL.len(),
+            TerrainType::ALL.len(),
+        )?;
+        writeln!(
+            writer,
+            r#"  <image source="{}" width="{}" height="{}"/>"#,
+            escape(tileset_source),
+            self.tileset.width(),
+            self.tileset.height(),
+        )?;
+
+        for (id, terrain) in TerrainType::ALL.into_iter().enumerate() {
+            writeln!(writer, r#"  <tile id="{id}">"#)?;
+            writeln!(writer, "   <properties>")?;
+            writeln!(
+                writer,
+                r#"    <property name="terrain" value="{terrain:?}"/>"#
+            )?;
+            writeln!(writer, "   </properties>")?;
+            writeln!(writer, "  </tile>")?;
+        }
+
+        writeln!(writer, " </tileset>")?;
+        writeln!(
+            writer,
+            r#" <layer id="1" name="Terrain" width="{}" height="{}">"#,
+            self.width, self.height
+        )?;
+        writeln!(writer, r#"  <data encoding="csv">"#)?;
+
+        for (row, tiles) in self.tiles.chunks(self.width as usize).enumerate() {
+            let line = tiles
+                .iter()
+                .map(|tile| (tile + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            // Every row but the last ends with a comma
+            let separator = if row + 1 < self.height as usize {
+                ","
+            } else {
+                ""
+            };
+            writeln!(writer, "{line}{separator}")?;
+        }
+
+        writeln!(writer, "  </data>")?;
+        writeln!(writer, " </layer>")?;
+        writeln!(writer, r#" <objectgroup id="2" name="Settlements">"#)?;
+
+        for (id, (name, position)) in self.settlements.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"  <object id="{}" name="{}" x="{}" y="{}">"#,
+                id + 1,
+                escape(name),
+                position.x,
+                position.y
+            )?;
+            writeln!(writer, "   <point/>")?;
+            writeln!(writer, "  </object>")?;
+        }
+
+        writeln!(writer, " </objectgroup>")?;
+        writeln!(writer, "</map>")?;
+
+        Ok(())
+    }
+
+    pub fn write_tmj(&self, writer: impl Write, tileset_source: &str) -> io::Result<()> {
+        let [tile_width, tile_height, hex_side] = self.tile_size;
+
+        let tiles = TerrainType::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(id, terrain)| {
+                json!({
+                    "id": id,
+                    "properties": [
+                        { "name": "terrain", "type": "string", "value": format!("{terrain:?}") },
+                    ],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let objects = self
+            .settlements
+            .iter()
+            .enumerate()
+            .map(|(id, (name, position))| {
+                json!({
+                    "id": id + 1,
+                    "name": name,
+                    "type": "",
+                    "point": true,
+                    "x": position.x,
+                    "y": position.y,
+                    "width": 0,
+                    "height": 0,
+                    "rotation": 0,
+                    "visible": true,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let map = json!({
+            "type": "map",
+            "version": FORMAT_VERSION,
+            "orientation": "hexagonal",
+            "renderorder": "right-down",
+            "width": self.width,
+            "height": self.height,
+            "tilewidth": tile_width,
+            "tileheight": tile_height,
+            "hexsidelength": hex_side,
+            "staggeraxis": self.stagger_axis(),
+            "staggerindex": self.stagger_index(),
+            "infinite": false,
+            "nextlayerid": 3,
+            "nextobjectid": self.settlements.len() + 1,
+            "properties": [
+                { "name": "topology", "type": "string", "value": format!("{:?}", self.topology) },
+            ],
+            "tilesets": [{
+                "firstgid": 1,
+                "name": TILESET_NAME,
+                "tilewidth": tile_width,
+                "tileheight": tile_height,
+                "tilecount": TerrainType::ALL.len(),
+                "columns": TerrainType::ALL.len(),
+                "margin": 0,
+                "spacing": 0,
+                "image": tileset_source,
+                "imagewidth": self.tileset.width(),
+                "imageheight": self.tileset.height(),
+                "tiles": tiles,
+            }],
+            "layers": [
+                {
+                    "type": "tilelayer",
+                    "id": 1,
+                    "name": "Terrain",
+                    "width": self.width,
+                    "height": self.height,
+                    "x": 0,
+                    "y": 0,
+                    "opacity": 1,
+                    "visible": true,
+                    "data": self.tiles.iter().map(|tile| tile + 1).collect::<Vec<_>>(),
+                },
+                {
+                    "type": "objectgroup",
+                    "id": 2,
+                    "name": "Settlements",
+                    "draworder": "topdown",
+                    "x": 0,
+                    "y": 0,
+                    "opacity": 1,
+                    "visible": true,
+                    "objects": objects,
+                },
+            ],
+        });
+
+        serde_json::to_writer(writer, &map)?;
+        Ok(())
+    }
+
+    // Writes the map as TMX or TMJ depending on the extension of `path`, with the tileset image
+    // saved next to it
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("map");
+        let tileset_source = format!("{stem}.tileset.png");
+
+        self.tileset.write_png(BufWriter::new(File::create(
+            path.with_file_name(&tileset_source),
+        )?))?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("tmx") => self.write_tmx(&mut writer, &tileset_source)?,
+            _ => self.write_tmj(&mut writer, &tileset_source)?,
+        }
+
+        writer.flush()
+    }
+}
+
+pub fn export(
+    terrain: &GeneratedTerrain,
+    TiledParams {
+        hex_size,
+        settlements,
+    }: TiledParams,
+) -> TiledMap {
+    let grid = terrain.grid();
+    let orientation = grid.orientation();
+    let (flip_rows, stagger_even) = tiled_layout(terrain);
+
+    let across = (3f32.sqrt() * hex_size as f32).round() as u32;
+    let tile_size = match orientation {
+        HexOrientation::Flat => [hex_size * 2, across, hex_size],
+        HexOrientation::Pointy => [across, hex_size * 2, hex_size],
+    };
+
+    let row = |y: i32| {
+        if flip_rows { grid.height() - y } else { y - 1 }
+    };
+
+    let mut tiles = vec![0; grid.len()];
+
+    for (hex, terrain) in terrain.tiles() {
+        let [x, y] = grid.hex_to_cell(hex);
+        let index = (row(y) * grid.width() + x - 1) as usize;
+
+        tiles[index] = TerrainType::ALL
+            .iter()
+            .position(|candidate| *candidate == terrain)
+            .unwrap();
+    }
+
+    let settlements = settlements
+        .into_iter()
+        .map(|(hex, name)| {
+            let [x, y] = grid.hex_to_cell(grid.wrap(hex));
+            let centre = tile_centre(
+                orientation,
+                stagger_even,
+                tile_size.map(|size| size as f32),
+                [x - 1, row(y)],
+            );
+
+            (name, centre)
+        })
+        .collect();
+
+    TiledMap {
+        orientation,
+        topology: grid.topology(),
+        width: grid.width(),
+        height: grid.height(),
+        tile_size,
+        stagger_even,
+        tiles,
+        settlements,
+        tileset: tileset_image(orientation, tile_size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hexx::OffsetHexMode;
+
+    use super::*;
+
+    use crate::terrain::{self, TerrainParams};
+
+    #[test]
+    fn tiles_sit_where_the_layout_puts_them() {
+        for orientation in [HexOrientation::Flat, HexOrientation::Pointy] {
+            for offset_mode in [OffsetHexMode::Even, OffsetHexMode::Odd] {
+                let terrain = terrain::generate(
+                    TerrainParams::new(10, 8, 1.2, 1)
+                        .with_orientation(orientation)
+                        .with_offset_mode(offset_mode),
+                );
+
+                let grid = terrain.grid();
+                let layout = terrain.layout();
+                let (flip_rows, stagger_even) = tiled_layout(&terrain);
+
+                let rect = layout.rect_size();
+                let size = match orientation {
+                    HexOrientation::Flat => [rect.x, rect.y, rect.x / 2.],
+                    HexOrientation::Pointy => [rect.x, rect.y, rect.y / 2.],
+                };
+
+                let screen = |column: i32, row: i32| {
+                    let y = if flip_rows { 8 - row } else { row + 1 };
+                    let position = layout.hex_to_world_pos(grid.cell_to_hex([column + 1, y]));
+                    Vec2::new(position.x, -position.y)
+                };
+
+                let origin = tile_centre(orientation, stagger_even, size, [0, 0]) - screen(0, 0);
+
+                for column in 0..10 {
+                    for row in 0..8 {
+                        let expected = screen(column, row) + origin;
+                        let actual = tile_centre(orientation, stagger_even, size, [column, row]);
+
+                        assert!(expected.distance(actual) < 1e-3);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    heightmap::Heightmap,
+    names::NameGenerator,
+    nations::{self, NationParams},
+    pathfinding::ZoneCost,
+    save::{GenerationParams, SavedNation, SavedSettlement, TerrainSource, WorldFile},
+    settlements::{self, SettlementParams},
+    sphere,
+    terrain::{self, GeneratedTerrain, TerrainParams},
+    territories,
+};
+
+// Generates a whole world from its terrain source, ready to save. Settlements and nations are
+// named by `names`, or numbered without one.
+pub fn generate(source: TerrainSource, names: Option<&NameGenerator>) -> WorldFile {
+    let terrain = match &source {
+        TerrainSource::Map(params) => terrain::generate(params.clone()),
+        TerrainSource::Globe {
+            sphere,
+            width,
+            height,
+        } => sphere::generate(sphere.clone()).project(*width, *height),
+    };
+
+    populate(source, terrain, names)
+}
+
+// Generates a whole world with its elevation taken from a heightmap
+pub fn generate_from_heightmap(
+    params: TerrainParams,
+    heightmap: &Heightmap,
+    names: Option<&NameGenerator>,
+) -> WorldFile {
+    let terrain = terrain::generate_from_heightmap(params.clone(), heightmap);
+
+    populate(TerrainSource::Map(params), terrain, names)
+}
+
+fn populate(
+    source: TerrainSource,
+    terrain: GeneratedTerrain,
+    names: Option<&NameGenerator>,
+) -> WorldFile {
+    // Every random choice made here comes from the world seed, on a separate stream from the
+    // terrain generation so the two don't share values
+    let mut rng = ChaCha8Rng::seed_from_u64(source.seed());
+    rng.set_stream(1);
+
+    let settlement_params = SettlementParams::new(rng.random());
+
+    let settlements = settlements::generate(&terrain, settlement_params).collect::<Vec<_>>();
+
+    // Nations are named from the same supply as settlements, so no name is used twice. Once it
+    // runs dry, the rest are numbered instead.
+    let mut names = names.map(|names| names.names(rng.random()));
+    let mut name = |fallback: String| names.as_mut().and_then(Iterator::next).unwrap_or(fallback);
+
+    let named_settlements = settlements
+        .iter()
+        .enumerate()
+        .map(|(index, settlement)| {
+            SavedSettlement::new(*settlement, name(format!("Settlement {}", index + 1)))
+        })
+        .collect::<Vec<_>>();
+
+    let seats = settlements
+        .iter()
+        .map(|settlement| settlement.hex)
+        .collect::<Vec<_>>();
+
+    let territories = territories::generate(&terrain, &seats, ZoneCost);
+
+    let nations = nations::generate(
+        &terrain,
+        &settlements,
+        &territories,
+        ZoneCost,
+        NationParams::default(),
+    );
+
+    let named_nations = nations
+        .nations()
+        .iter()
+        .enumerate()
+        .map(|(index, nation)| SavedNation::new(nation, name(format!("Nation {}", index + 1))))
+        .collect::<Vec<_>>();
+
+    WorldFile::new(GenerationParams::new(source, settlement_params), terrain)
+        .with_settlements(named_settlements)
+        .with_zones(territories.zones())
+        .with_nations(named_nations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_settlement_is_named_when_names_run_out() {
+        let source = TerrainSource::Map(TerrainParams::new(80, 48, 1.2, 3));
+        let names = NameGenerator::new(["Ash", "Ashby", "Ashford"]).with_source_names();
+
+        let unnamed = generate(source.clone(), None);
+        let named = generate(source, Some(&names));
+
+        assert_eq!(named.settlements().len(), unnamed.settlements().len());
+        assert_eq!(named.nations().len(), unnamed.nations().len());
+        assert!(named.settlements().len() > 3);
+
+        let mut supplied = named.settlements()[..3]
+            .iter()
+            .map(|settlement| settlement.name.as_str())
+            .collect::<Vec<_>>();
+        supplied.sort();
+
+        assert_eq!(supplied, ["Ash", "Ashby", "Ashford"]);
+        assert_eq!(named.settlements()[3].name, "Settlement 4");
+    }
+}
@@ -4,10 +4,13 @@ use bevy::prelude::*;
 #[require(Transform)]
 pub enum RenderOrder {
     Terrain,
+    River,
+    Road,
     InHex,
     Overlay,
     Selection,
     Border,
+    NationBorder,
     WorldUi,
 }
 
@@ -17,6 +20,7 @@ pub enum OverlayMode {
     #[default]
     None,
     Zone,
+    Nations,
 }
 
 #[derive(Clone, Copy, Default, Resource)]
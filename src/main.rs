@@ -7,13 +7,19 @@ use bevy::{
 use bevy::remote::{RemotePlugin, http::RemoteHttpPlugin};
 
 use bevy_asset_loader::prelude::*;
+use hexx::{HexOrientation, OffsetHexMode};
+
+use hexmap_worldgen::terrain::Topology;
+
+#[cfg(not(target_arch = "wasm32"))]
+use hexmap_worldgen::save::{self, WorldFileError};
 
 use camera::{CameraPlugin, CurrentOverlay, OverlayMode};
 use input::InputPlugin;
 use profiling::ProfilingPlugin;
 use selection::SelectionPlugin;
 use ui::UiPlugin;
-use world::{WorldLayout, WorldOrigin, WorldParams, WorldPlugin, WorldTiles};
+use world::{CurrentWorld, WorldLayout, WorldOrigin, WorldParams, WorldPlugin, WorldTiles};
 
 mod camera;
 mod input;
@@ -29,26 +35,105 @@ pub enum AppState {
     Main,
 }
 
-fn setup_world(mut commands: Commands) {
+fn request_world(commands: &mut Commands, seed: u64) {
+    // Flat-top hexes unless pointy-top ones are asked for
+    let orientation = match std::env::var("HEXMAP_ORIENTATION").as_deref() {
+        Ok("pointy") => HexOrientation::Pointy,
+        _ => HexOrientation::Flat,
+    };
+
+    // Wrapping east to west unless another topology is asked for
+    let topology = match std::env::var("HEXMAP_TOPOLOGY").as_deref() {
+        Ok("flat") => Topology::Flat,
+        Ok("torus") => Topology::Torus,
+        _ => Topology::Cylinder,
+    };
+
+    // Generate on a globe if asked for, with the given number of subdivisions
+    let globe = std::env::var("HEXMAP_GLOBE")
+        .ok()
+        .and_then(|subdivisions| subdivisions.parse().ok());
+
     // Request the world generation
     commands.insert_resource(WorldParams {
         width: 170,
         height: 100,
         scale_factor: 1.2,
+        seed,
+        orientation,
+        offset_mode: OffsetHexMode::Even,
+        topology,
+        globe,
     });
 }
 
-fn regenerate_world(mut commands: Commands, grid: Single<Entity, With<WorldOrigin>>) {
+fn setup_world(mut commands: Commands) {
+    // Allow a specific world to be reproduced by supplying its seed
+    let seed = std::env::var("HEXMAP_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random);
+
+    request_world(&mut commands, seed);
+}
+
+fn clear_world(commands: &mut Commands, grid: Entity) {
     // Cleanup the previous world
     commands.remove_resource::<WorldLayout>();
     commands.remove_resource::<WorldTiles>();
-    commands.entity(grid.into_inner()).despawn();
+    commands.remove_resource::<CurrentWorld>();
+    commands.entity(grid).despawn();
 
     // Reset the current overlay
     commands.insert_resource(CurrentOverlay::default());
+}
+
+fn regenerate_world(mut commands: Commands, grid: Single<Entity, With<WorldOrigin>>) {
+    clear_world(&mut commands, grid.into_inner());
 
     // Re-request a world
-    setup_world(commands);
+    request_world(&mut commands, rand::random());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path() -> std::path::PathBuf {
+    std::env::var("HEXMAP_SAVE")
+        .unwrap_or_else(|_| "world.json".into())
+        .into()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_world(world: Res<CurrentWorld>) {
+    let path = save_path();
+
+    match std::fs::File::create(&path)
+        .map_err(WorldFileError::from)
+        .and_then(|file| save::save(&world.0, file))
+    {
+        Ok(()) => info!("Saved world to {}", path.display()),
+        Err(error) => error!("Failed to save world to {}: {error}", path.display()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_world(mut commands: Commands, grid: Single<Entity, With<WorldOrigin>>) {
+    let path = save_path();
+
+    let world = match std::fs::File::open(&path)
+        .map_err(WorldFileError::from)
+        .and_then(save::load)
+    {
+        Ok(world) => world,
+        Err(error) => {
+            error!("Failed to load world from {}: {error}", path.display());
+            return;
+        }
+    };
+
+    info!("Loaded world from {}", path.display());
+
+    clear_world(&mut commands, grid.into_inner());
+    commands.insert_resource(CurrentWorld(world));
 }
 
 fn mode_toggle(keyboard_input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<CurrentOverlay>) {
@@ -56,6 +141,8 @@ fn mode_toggle(keyboard_input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<Curre
         mode.0 = OverlayMode::None
     } else if keyboard_input.just_released(KeyCode::Digit2) {
         mode.0 = OverlayMode::Zone
+    } else if keyboard_input.just_released(KeyCode::Digit3) {
+        mode.0 = OverlayMode::Nations
     }
 }
 
@@ -101,6 +188,18 @@ pub fn main() {
     .add_systems(
         PostUpdate,
         regenerate_world.run_if(input_just_released(KeyCode::Space)),
-    )
-    .run();
+    );
+
+    // Saving and loading need a filesystem
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(
+        PostUpdate,
+        (
+            save_world
+                .run_if(resource_exists::<CurrentWorld>.and(input_just_released(KeyCode::F5))),
+            load_world.run_if(input_just_released(KeyCode::F9)),
+        ),
+    );
+
+    app.run();
 }
@@ -5,11 +5,13 @@ use bevy::{
     text::TextLayoutInfo,
 };
 
+use hexmap_worldgen::settlements::SettlementTier;
+
 use crate::{camera::RenderOrder, world::WorldLayout};
 
 #[derive(Clone, Component)]
 #[component(on_insert = on_insert)]
-pub struct SettlementUi(pub String);
+pub struct SettlementUi(pub String, pub SettlementTier);
 
 #[derive(Resource)]
 struct BannerMesh(Handle<Mesh>);
@@ -26,9 +28,15 @@ fn on_insert(mut world: DeferredWorld, context: HookContext) {
 
     let scale = world.resource::<WorldLayout>().layout.scale;
 
+    let font_size = match component.1 {
+        SettlementTier::Village => 44.,
+        SettlementTier::Town => 64.,
+        SettlementTier::City => 88.,
+    };
+
     world.commands().spawn((
         RenderOrder::WorldUi,
-        TextFont::default().with_font_size(64.),
+        TextFont::default().with_font_size(font_size),
         TextColor::WHITE,
         Text2d(component.0),
         ChildOf(context.entity),
@@ -1,12 +1,16 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_common_assets::csv::LoadedCsv;
+use hexmap_worldgen::{names::NameGenerator, terrain::TerrainClassifier};
 use serde::Deserialize;
 
 #[derive(AssetCollection, Resource)]
 pub struct WorldGenerationAssets {
     #[asset(path = "settlement_names.csv")]
     names: Handle<LoadedCsv<SettlementName>>,
+
+    #[asset(path = "default.terrain.ron")]
+    terrain: Handle<TerrainPreset>,
 }
 
 #[derive(Deserialize, Asset, TypePath, Clone)]
@@ -14,18 +18,46 @@ pub struct SettlementName {
     pub name: String,
 }
 
+// Made-up names in the style of the ones in `settlement_names.csv`
 #[derive(Resource, Clone)]
-pub struct SettlementNames(pub Vec<SettlementName>);
+pub struct SettlementNames(pub NameGenerator);
 
 impl FromWorld for SettlementNames {
     fn from_world(world: &mut World) -> Self {
-        let names = world
+        let names = &world
             .resource::<Assets<LoadedCsv<SettlementName>>>()
             .get(world.resource::<WorldGenerationAssets>().names.id())
             .unwrap()
-            .rows
+            .rows;
+
+        SettlementNames(NameGenerator::new(names.iter().map(|row| &row.name)))
+    }
+}
+
+#[derive(Deserialize, Asset, TypePath, Clone)]
+#[serde(transparent)]
+pub struct TerrainPreset(pub TerrainClassifier);
+
+#[derive(Resource, Clone)]
+pub struct TerrainClassification(pub TerrainClassifier);
+
+impl FromWorld for TerrainClassification {
+    fn from_world(world: &mut World) -> Self {
+        let preset = world
+            .resource::<Assets<TerrainPreset>>()
+            .get(world.resource::<WorldGenerationAssets>().terrain.id())
+            .unwrap()
+            .0
             .clone();
 
-        SettlementNames(names)
+        // A broken preset would misclassify or panic partway through generation, so fall back to
+        // the default instead
+        if let Err(error) = preset.validate() {
+            error!("Invalid terrain preset, using the default instead: {error}");
+
+            return TerrainClassification(TerrainClassifier::default());
+        }
+
+        TerrainClassification(preset)
     }
 }
@@ -1,5 +1,3 @@
-use std::{collections::VecDeque, num::NonZero};
-
 use bevy::{
     asset::RenderAssetUsages,
     platform::collections::{HashMap, hash_map::Entry},
@@ -8,60 +6,32 @@ use bevy::{
 };
 
 use hexx::{GridEdge, Hex, HexLayout, PlaneMeshBuilder};
-use rand::{Rng, rng, seq::IndexedRandom};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
 use hexmap_worldgen::{
-    settlements::{self, SettlementParams},
-    terrain::{self, TerrainParams, TerrainType},
+    palette::terrain_colour,
+    rivers::{self, RiverParams},
+    roads::{self, RoadParams},
+    save::TerrainSource,
+    settlements::SettlementTier,
+    sphere::SphereParams,
+    terrain::{ErosionParams, TerrainParams},
+    world,
 };
 
 use crate::{
-    camera::RenderOrder,
+    camera::{OverlayMode, RenderOrder},
     ui::SettlementUi,
-    world::{OnHex, ZoneHighlight, assets::SettlementNames},
+    world::{
+        NationHighlight, OnHex, ZoneHighlight,
+        assets::{SettlementNames, TerrainClassification},
+    },
 };
 
-use super::{WorldColumn, WorldLayout, WorldOrigin, WorldParams, WorldTiles};
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct ClosestZone {
-    zone: usize,
-    cost: NonZero<usize>,
-}
-
-impl ClosestZone {
-    fn new(zone: usize, cost: NonZero<usize>) -> Self {
-        Self { zone, cost }
-    }
-}
-
-fn terrain_colour(colour: TerrainType) -> [u8; 4] {
-    match colour {
-        TerrainType::DeepOcean => [6, 58, 127, 255],
-        TerrainType::ShallowOcean => [14, 112, 192, 255],
-        TerrainType::Coast => [25, 150, 230, 255],
-        TerrainType::Beach => [210, 170, 110, 255],
-        TerrainType::Plains => [70, 120, 60, 255],
-        TerrainType::Hills => [110, 140, 100, 255],
-        TerrainType::LowMountains => [150, 150, 150, 255],
-        TerrainType::HighMountains => [220, 220, 200, 255],
-        TerrainType::Peaks => [250, 250, 250, 255],
-    }
-}
-
-fn terrain_zone_cost(terrain: TerrainType) -> usize {
-    match terrain {
-        TerrainType::DeepOcean => 500,
-        TerrainType::ShallowOcean => 100,
-        TerrainType::Coast => 50,
-        TerrainType::Beach => 2,
-        TerrainType::Plains => 2,
-        TerrainType::Hills => 3,
-        TerrainType::LowMountains => 100,
-        TerrainType::HighMountains => 500,
-        TerrainType::Peaks => 1000,
-    }
-}
+use super::{
+    CurrentWorld, WorldColumn, WorldLayout, WorldOrigin, WorldParams, WorldTile, WorldTiles,
+};
 
 fn hexagonal_plane(hex_layout: &HexLayout) -> Mesh {
     let mesh_info = PlaneMeshBuilder::new(hex_layout)
@@ -82,19 +52,57 @@ pub fn generate_world(
     mut commands: Commands,
     params: Res<WorldParams>,
     settlement_names: Res<SettlementNames>,
+    classification: Res<TerrainClassification>,
+) {
+    info!("Generating world with seed {}", params.seed);
+
+    let terrain_source = match params.globe {
+        // Globes are generated on the sphere and unwrapped onto a regular flat-top map
+        Some(subdivisions) => TerrainSource::Globe {
+            sphere: SphereParams::new(subdivisions, params.scale_factor, params.seed)
+                .with_classifier(classification.0.clone())
+                .with_erosion(ErosionParams::default()),
+            width: params.width,
+            height: params.height,
+        },
+        None => TerrainSource::Map(
+            TerrainParams::new(
+                params.width,
+                params.height,
+                params.scale_factor,
+                params.seed,
+            )
+            .with_orientation(params.orientation)
+            .with_offset_mode(params.offset_mode)
+            .with_topology(params.topology)
+            .with_classifier(classification.0.clone())
+            .with_erosion(ErosionParams::default())
+            .with_depression_filling(0.02),
+        ),
+    };
+
+    let world_file = world::generate(terrain_source, Some(&settlement_names.0));
+
+    commands.remove_resource::<WorldParams>();
+    commands.insert_resource(CurrentWorld(world_file));
+}
+
+pub fn spawn_world(
+    mut commands: Commands,
+    current: Res<CurrentWorld>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    assert_eq!(size_of::<ClosestZone>(), size_of::<Option<ClosestZone>>());
+    let generated_terrain = current.0.terrain();
 
-    let generated_terrain = terrain::generate(TerrainParams::new(
-        params.width,
-        params.height,
-        params.scale_factor,
-    ));
+    // Zone colours are only for display, so they come from their own stream of the world seed
+    // rather than being saved with the world
+    let mut rng = ChaCha8Rng::seed_from_u64(current.0.params().seed());
+    rng.set_stream(2);
 
     let world = WorldLayout {
         layout: generated_terrain.layout().clone().with_hex_size(6.),
+        grid: generated_terrain.grid(),
         width: generated_terrain.width(),
         height: generated_terrain.height(),
     };
@@ -125,8 +133,9 @@ pub fn generate_world(
 
     let tiles = generated_terrain
         .tiles()
-        .map(|(hex, terrain)| {
-            let colour = terrain_colour(terrain);
+        .zip(generated_terrain.biomes())
+        .map(|((hex, terrain), (_, biome))| {
+            let colour = terrain_colour(terrain, biome);
             let mesh = match mesh_cache.entry(colour) {
                 Entry::Occupied(mesh) => mesh.get().clone(),
                 Entry::Vacant(vacant) => vacant
@@ -148,11 +157,15 @@ pub fn generate_world(
             let [x, _] = world.hex_to_xy(hex);
             let pos = world.layout.hex_to_world_pos(hex);
 
+            // Pointy-top rows are staggered, so tiles can sit off the centre of their column
+            let column_x = world.xy_to_world_pos(x, 0).x;
+
             commands
                 .spawn((
                     Mesh2d(mesh),
                     MeshMaterial2d(material.clone()),
-                    Transform::from_xyz(0., pos.y, 0.),
+                    WorldTile { hex },
+                    Transform::from_xyz(pos.x - column_x, pos.y, 0.),
                     ChildOf(columns[x as usize - 1]),
                     RenderOrder::Terrain,
                 ))
@@ -160,138 +173,159 @@ pub fn generate_world(
         })
         .collect();
 
-    let terrain = generated_terrain.tiles().collect::<HashMap<_, _>>();
-
     let settlement_material =
         materials.add(ColorMaterial::from_color(Color::srgb_u8(100, 50, 150)));
 
-    let settlement_mesh = meshes.add(Rectangle::new(6., 6.));
-
-    let settlements =
-        settlements::generate(&generated_terrain, SettlementParams::new(rng().random()))
-            .collect::<VecDeque<_>>();
+    let village_mesh = meshes.add(Rectangle::new(4., 4.));
+    let town_mesh = meshes.add(Rectangle::new(6., 6.));
+    let city_mesh = meshes.add(Rectangle::new(8.5, 8.5));
 
-    let zone_colours = settlements
+    let zone_colours = current
+        .0
+        .settlements()
         .iter()
-        .map(|_| [rng().random(), rng().random(), rng().random(), 80])
+        .map(|_| [rng.random(), rng.random(), rng.random(), 80])
         .collect::<Vec<[u8; 4]>>();
 
-    let mut closest_zones = settlements
+    let nation_colours = current
+        .0
+        .nations()
         .iter()
-        .enumerate()
-        .map(|(index, hex)| (*hex, ClosestZone::new(index, NonZero::new(1).unwrap())))
-        .collect::<HashMap<_, _>>();
-
-    for (hex, name) in settlements.iter().zip(
-        settlement_names
-            .0
-            .choose_multiple(&mut rand::rng(), settlements.len()),
-    ) {
-        let mut hex = *hex;
-        hex.x -= 1;
-        hex.y -= 1;
+        .map(|_| [rng.random(), rng.random(), rng.random(), 110])
+        .collect::<Vec<[u8; 4]>>();
+
+    let mut zone_nations = vec![None; current.0.settlements().len()];
+
+    for (index, nation) in current.0.nations().iter().enumerate() {
+        for zone in &nation.zones {
+            zone_nations[*zone] = Some(index);
+        }
+    }
+
+    for settlement in current.0.settlements() {
+        let mesh = match settlement.tier {
+            SettlementTier::Village => &village_mesh,
+            SettlementTier::Town => &town_mesh,
+            SettlementTier::City => &city_mesh,
+        };
 
         commands.spawn((
             RenderOrder::InHex,
-            Mesh2d(settlement_mesh.clone()),
+            Mesh2d(mesh.clone()),
             MeshMaterial2d(settlement_material.clone()),
-            OnHex(Some(hex)),
+            OnHex(Some(settlement.hex)),
         ));
 
-        commands.spawn((SettlementUi(name.name.clone()), OnHex(Some(hex))));
+        commands.spawn((
+            SettlementUi(settlement.name.clone(), settlement.tier),
+            OnHex(Some(settlement.hex)),
+        ));
     }
 
-    let mut frontier = settlements;
+    let closest_zones = current.0.zones().collect::<HashMap<_, _>>();
 
-    let cost_fn = |from, to| {
-        let next_cost =
-            if let Some(cost) = terrain.get(&to).map(|terrain| terrain_zone_cost(*terrain)) {
-                cost
-            } else {
-                return None;
-            };
+    let edge_width = 0.8;
+    let edge_mesh = meshes.add(Rectangle::new(
+        world.layout.scale.x + (edge_width / 2.),
+        edge_width,
+    ));
+    let edge_material = materials.add(ColorMaterial::from_color(Color::BLACK));
+    let nation_edge_material = materials.add(ColorMaterial::from_color(Color::srgb_u8(90, 20, 30)));
 
-        let this_cost = terrain_zone_cost(*terrain.get(&from).unwrap());
+    let river_material = materials.add(ColorMaterial::from_color(Color::srgb_u8(25, 150, 230)));
 
-        Some(if next_cost > this_cost {
-            next_cost
-        } else {
-            next_cost / 2
-        })
-    };
+    for segment in rivers::generate(generated_terrain, RiverParams::default()).segments() {
+        let edge = GridEdge {
+            origin: Hex::new(0, 0),
+            direction: segment.edge.direction,
+        };
 
-    while let Some(hex) = frontier.pop_front() {
-        let current = *closest_zones.get(&hex).unwrap();
+        let [a, b] = world.layout.edge_coordinates(edge);
+        let midpoint = a.midpoint(b);
+        let rotation = midpoint.perp().to_angle();
+        let width = (1. + segment.flow.log10() / 2.).min(3.) as f32;
 
-        for neighbour in hex.all_neighbors() {
-            let neighbour = world.wrap(neighbour);
+        commands.spawn((
+            Mesh2d(edge_mesh.clone()),
+            MeshMaterial2d(river_material.clone()),
+            OnHex(Some(segment.edge.origin)),
+            Transform::from_translation(midpoint.extend(1.))
+                .with_rotation(Quat::from_rotation_z(rotation))
+                .with_scale(Vec3::new(1., width, 1.)),
+            RenderOrder::River,
+        ));
+    }
 
-            let cost = if let Some(cost) = cost_fn(hex, neighbour) {
-                cost
-            } else {
-                continue;
-            };
+    let road_material = materials.add(ColorMaterial::from_color(Color::srgb_u8(110, 80, 50)));
+    let road_mesh = meshes.add(Rectangle::new(1., 1.));
 
-            let neighbour_cost = NonZero::new(current.cost.get() + cost).unwrap();
+    let seats = current
+        .0
+        .settlements()
+        .iter()
+        .map(|settlement| settlement.hex)
+        .collect::<Vec<_>>();
 
-            match closest_zones.entry(neighbour) {
-                Entry::Occupied(entry) => {
-                    let existing_cost = entry.get().cost;
+    for segment in roads::generate(generated_terrain, &seats, RoadParams::default()).segments() {
+        // Roads run straight from the centre of one hex to the centre of the next
+        let towards = world
+            .layout
+            .hex_to_world_pos(Hex::new(0, 0).neighbor(segment.edge.direction))
+            - world.layout.hex_to_world_pos(Hex::new(0, 0));
+        let width = (0.6 + (segment.traffic as f32).log2() / 4.).min(1.4);
 
-                    if neighbour_cost < existing_cost {
-                        entry.replace_entry_with(|_, _| {
-                            Some(ClosestZone::new(current.zone, neighbour_cost))
-                        });
-                        frontier.push_back(neighbour);
-                    }
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(ClosestZone::new(current.zone, neighbour_cost));
-                    frontier.push_back(neighbour);
-                }
-            }
-        }
+        commands.spawn((
+            Mesh2d(road_mesh.clone()),
+            MeshMaterial2d(road_material.clone()),
+            OnHex(Some(segment.edge.origin)),
+            Transform::from_translation((towards / 2.).extend(1.))
+                .with_rotation(Quat::from_rotation_z(towards.to_angle()))
+                .with_scale(Vec3::new(towards.length(), width, 1.)),
+            RenderOrder::Road,
+        ));
     }
 
-    let edge_width = 0.8;
-    let edge_mesh = meshes.add(Rectangle::new(
-        world.layout.scale.x + (edge_width / 2.),
-        edge_width,
-    ));
-    let edge_material = materials.add(ColorMaterial::from_color(Color::BLACK));
+    let mut overlay_mesh = |colour: [u8; 4]| match mesh_cache.entry(colour) {
+        Entry::Occupied(mesh) => mesh.get().clone(),
+        Entry::Vacant(vacant) => vacant
+            .insert({
+                let colour = Color::srgba_u8(colour[0], colour[1], colour[2], colour[3]);
+                let mut mesh = base_mesh.clone();
+                mesh.insert_attribute(
+                    Mesh::ATTRIBUTE_COLOR,
+                    VertexAttributeValues::Float32x4(vec![
+                        colour.to_srgba().to_f32_array();
+                        mesh.count_vertices()
+                    ]),
+                );
+                meshes.add(mesh)
+            })
+            .clone(),
+    };
 
     for (hex, zone) in &closest_zones {
-        let on_hex = OnHex(Some(*hex - Hex::new(1, 1)));
-
-        let colour = zone_colours[zone.zone];
-        let mesh = match mesh_cache.entry(colour) {
-            Entry::Occupied(mesh) => mesh.get().clone(),
-            Entry::Vacant(vacant) => vacant
-                .insert({
-                    let colour = Color::srgba_u8(colour[0], colour[1], colour[2], colour[3]);
-                    let mut mesh = base_mesh.clone();
-                    mesh.insert_attribute(
-                        Mesh::ATTRIBUTE_COLOR,
-                        VertexAttributeValues::Float32x4(vec![
-                            colour.to_srgba().to_f32_array();
-                            mesh.count_vertices()
-                        ]),
-                    );
-                    meshes.add(mesh)
-                })
-                .clone(),
-        };
+        let on_hex = OnHex(Some(*hex));
+        let nation = zone_nations[*zone];
 
         commands.spawn((
-            Mesh2d(mesh.clone()),
+            Mesh2d(overlay_mesh(zone_colours[*zone])),
             MeshMaterial2d(material.clone()),
             on_hex,
             ZoneHighlight,
         ));
 
+        if let Some(nation) = nation {
+            commands.spawn((
+                Mesh2d(overlay_mesh(nation_colours[nation])),
+                MeshMaterial2d(material.clone()),
+                on_hex,
+                NationHighlight,
+            ));
+        }
+
         for neighbour in &hex.all_neighbors()[..3] {
             if let Some(neighbour_zone) = closest_zones.get(&world.wrap(*neighbour)) {
-                if neighbour_zone.zone != zone.zone {
+                if neighbour_zone != zone {
                     let direction = hex.neighbor_direction(*neighbour).unwrap();
                     let edge = GridEdge {
                         origin: Hex::new(0, 0),
@@ -301,20 +335,32 @@ pub fn generate_world(
                     let [a, b] = world.layout.edge_coordinates(edge);
                     let midpoint = a.midpoint(b);
                     let rotation = midpoint.perp().to_angle();
+                    let transform = Transform::from_translation(midpoint.extend(1.))
+                        .with_rotation(Quat::from_rotation_z(rotation));
 
                     commands.spawn((
                         Mesh2d(edge_mesh.clone()),
                         MeshMaterial2d(edge_material.clone()),
                         on_hex,
-                        Transform::from_translation(midpoint.extend(1.))
-                            .with_rotation(Quat::from_rotation_z(rotation)),
+                        transform,
                         RenderOrder::Border,
                     ));
+
+                    // Nation borders are drawn thickly over the zone borders they follow
+                    if nation != zone_nations[*neighbour_zone] {
+                        commands.spawn((
+                            Mesh2d(edge_mesh.clone()),
+                            MeshMaterial2d(nation_edge_material.clone()),
+                            on_hex,
+                            transform.with_scale(Vec3::new(1., 3., 1.)),
+                            RenderOrder::NationBorder,
+                            OverlayMode::Nations,
+                        ));
+                    }
                 }
             }
         }
     }
 
-    commands.remove_resource::<WorldParams>();
     commands.insert_resource(WorldTiles { tiles });
 }
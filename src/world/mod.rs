@@ -1,10 +1,13 @@
 use bevy::{app::MainScheduleOrder, prelude::*};
 use bevy_asset_loader::prelude::*;
-use bevy_common_assets::csv::CsvAssetPlugin;
+use bevy_common_assets::{csv::CsvAssetPlugin, ron::RonAssetPlugin};
 
 use crate::{
     AppState,
-    world::assets::{SettlementName, SettlementNames, WorldGenerationAssets},
+    world::assets::{
+        SettlementName, SettlementNames, TerrainClassification, TerrainPreset,
+        WorldGenerationAssets,
+    },
 };
 
 pub use self::types::*;
@@ -24,16 +27,24 @@ impl Plugin for WorldPlugin {
             .resource_mut::<MainScheduleOrder>()
             .insert_before(PostUpdate, systems::GridUpdate);
 
-        app.add_plugins(CsvAssetPlugin::<SettlementName>::new(&["csv"]))
-            .configure_loading_state(
-                LoadingStateConfig::new(AppState::Loading)
-                    .load_collection::<WorldGenerationAssets>()
-                    .finally_init_resource::<SettlementNames>(),
-            );
+        app.add_plugins((
+            CsvAssetPlugin::<SettlementName>::new(&["csv"]),
+            RonAssetPlugin::<TerrainPreset>::new(&["terrain.ron"]),
+        ))
+        .configure_loading_state(
+            LoadingStateConfig::new(AppState::Loading)
+                .load_collection::<WorldGenerationAssets>()
+                .finally_init_resource::<SettlementNames>()
+                .finally_init_resource::<TerrainClassification>(),
+        );
 
         app.add_systems(
             Update,
-            generation::generate_world.run_if(resource_exists::<WorldParams>),
+            (
+                generation::generate_world.run_if(resource_exists::<WorldParams>),
+                generation::spawn_world.run_if(resource_added::<CurrentWorld>),
+            )
+                .chain(),
         )
         .add_systems(
             systems::GridUpdate,
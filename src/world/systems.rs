@@ -2,7 +2,7 @@ use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
 
 use crate::camera::VisibilityFlags;
 
-use super::{OnHex, WorldColumn, WorldLayout, WorldOrigin, WorldTiles};
+use super::{OnHex, WorldColumn, WorldLayout, WorldOrigin, WorldTile, WorldTiles};
 
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GridUpdate;
@@ -27,9 +27,24 @@ pub fn wrap_grid(
     world: Res<WorldLayout>,
     origin: Single<&mut Transform, (With<WorldOrigin>, Changed<Transform>)>,
     columns: Query<(&WorldColumn, &mut Transform), Without<WorldOrigin>>,
+    tiles: Query<(&WorldTile, &mut Transform), (Without<WorldOrigin>, Without<WorldColumn>)>,
 ) {
     let mut origin = origin.into_inner();
 
+    if world.grid.topology().wraps_x() {
+        wrap_columns(&world, &mut origin, columns);
+    }
+
+    if world.grid.topology().wraps_y() {
+        wrap_rows(&world, &mut origin, tiles);
+    }
+}
+
+fn wrap_columns(
+    world: &WorldLayout,
+    origin: &mut Transform,
+    columns: Query<(&WorldColumn, &mut Transform), Without<WorldOrigin>>,
+) {
     // Compute the width of the world in pixel coordinates
     let world_width = world.width();
 
@@ -56,3 +71,29 @@ pub fn wrap_grid(
         transform.translation.x = world.xy_to_world_pos(wrapped_column, 0).x;
     }
 }
+
+fn wrap_rows(
+    world: &WorldLayout,
+    origin: &mut Transform,
+    tiles: Query<(&WorldTile, &mut Transform), (Without<WorldOrigin>, Without<WorldColumn>)>,
+) {
+    let world_height = world.height();
+
+    // Ensure the grid offset is between 0 and the height of the world
+    if origin.translation.y < 0. {
+        origin.translation.y += world_height
+    }
+
+    if origin.translation.y > world_height {
+        origin.translation.y -= world_height
+    }
+
+    // Wrap any tile past the top edge of the world down to the bottom
+    let threshold = world_height - origin.translation.y;
+
+    for (tile, mut transform) in tiles {
+        let y = world.layout.hex_to_world_pos(tile.hex).y;
+
+        transform.translation.y = if y > threshold { y - world_height } else { y };
+    }
+}
@@ -2,12 +2,21 @@ use bevy::prelude::*;
 
 use hexx::{Hex, HexLayout, HexOrientation, OffsetHexMode};
 
+use hexmap_worldgen::{
+    save::WorldFile,
+    terrain::{HexGrid, Topology},
+};
+
 use crate::camera::{OverlayMode, RenderOrder, VisibilityFlags};
 
 #[derive(Copy, Clone, Default, Component)]
 #[require(Visibility, RenderOrder = RenderOrder::Overlay, OverlayMode = OverlayMode::Zone)]
 pub struct ZoneHighlight;
 
+#[derive(Copy, Clone, Default, Component)]
+#[require(Visibility, RenderOrder = RenderOrder::Overlay, OverlayMode = OverlayMode::Nations)]
+pub struct NationHighlight;
+
 #[derive(Copy, Clone, Default, Component)]
 #[require(VisibilityFlags, Transform)]
 pub struct OnHex(pub Option<Hex>);
@@ -22,9 +31,16 @@ pub struct WorldColumn {
     pub column: i32,
 }
 
+#[derive(Copy, Clone, Component)]
+pub struct WorldTile {
+    pub hex: Hex,
+}
+
 #[derive(Clone, Resource)]
 pub struct WorldLayout {
     pub layout: HexLayout,
+    // How the hexes are numbered and wrapped, the same as during generation
+    pub grid: HexGrid,
     pub width: i32,
     pub height: i32,
 }
@@ -34,8 +50,18 @@ pub struct WorldParams {
     pub width: i32,
     pub height: i32,
     pub scale_factor: f64,
+    pub seed: u64,
+    pub orientation: HexOrientation,
+    pub offset_mode: OffsetHexMode,
+    pub topology: Topology,
+    // Generate on a globe with this many subdivisions, rather than directly on the map
+    pub globe: Option<u32>,
 }
 
+// The world on screen, kept in the form it's saved in
+#[derive(Resource)]
+pub struct CurrentWorld(pub WorldFile);
+
 #[derive(Default, Resource)]
 pub struct WorldTiles {
     pub tiles: Vec<Entity>,
@@ -43,23 +69,15 @@ pub struct WorldTiles {
 
 impl WorldLayout {
     pub fn hex(&self, x: i32, y: i32) -> Hex {
-        Hex::from_offset_coordinates([x, y], OffsetHexMode::Even, HexOrientation::Flat)
+        Hex::from_offset_coordinates([x, y], self.grid.offset_mode(), self.layout.orientation)
     }
 
     pub fn hex_to_xy(&self, hex: Hex) -> [i32; 2] {
-        hex.to_offset_coordinates(OffsetHexMode::Even, HexOrientation::Flat)
+        self.grid.offset(hex)
     }
 
     pub fn wrap(&self, hex: Hex) -> Hex {
-        let [mut x, y] = self.hex_to_xy(hex);
-
-        if x <= 0 {
-            x += self.width;
-        } else if x > self.width {
-            x -= self.width;
-        }
-
-        self.hex(x, y)
+        self.grid.wrap(hex)
     }
 
     pub fn world_size(&self) -> Vec2 {
@@ -68,7 +86,12 @@ impl WorldLayout {
     }
 
     pub fn width(&self) -> f32 {
-        self.world_size().x
+        // Measured along a single row, since pointy-top rows are staggered
+        self.xy_to_world_pos(self.width, 0).x - self.xy_to_world_pos(0, 0).x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.xy_to_world_pos(0, self.height).y - self.xy_to_world_pos(0, 0).y
     }
 
     pub fn world_pos_to_xy(&self, pos: Vec2) -> [i32; 2] {
@@ -80,30 +103,17 @@ impl WorldLayout {
     }
 
     pub fn pick_tile(&self, world_pos: Vec2, origin: Vec2) -> Hex {
-        let mut hex = self.layout.world_pos_to_hex(world_pos - origin);
-        hex.x -= 1;
-        hex.y -= 1;
-
-        hex
+        self.layout.world_pos_to_hex(world_pos - origin)
     }
 }
 
 impl WorldTiles {
     pub fn get(&self, hex: Hex, world: &WorldLayout) -> Option<Entity> {
-        let [mut x, y] = world.hex_to_xy(hex);
-
-        if x < 0 {
-            x += world.width;
-        } else if x >= world.width {
-            x -= world.width;
-        }
-
-        let index = (x * world.height + y) as usize;
-
-        if index >= self.tiles.len() {
-            None
-        } else {
-            Some(self.tiles[index])
-        }
+        // Tiles are spawned in the same order as the terrain's tiles
+        world
+            .grid
+            .hex_to_index(world.wrap(hex))
+            .and_then(|index| self.tiles.get(index))
+            .copied()
     }
 }